@@ -0,0 +1,25 @@
+use rgfx::prelude::*;
+
+fn main() {
+    let mut renderer = HeadlessRenderer::new(800, 600).expect("Unable to create headless renderer");
+    println!("Adapter: {:?}", renderer.adapter_info());
+
+    let mut canvas = renderer.canvas();
+    canvas.clear(Color::WHITE);
+
+    let camera = &mut Camera::default_orthographic(800.0, 600.0);
+    camera.update_viewport(800.0, 600.0);
+
+    // Draw a triangle
+    let transform = Transform::new()
+        .with_scale(0.75, 0.75, 0.75);
+    canvas.draw_triangle(camera, transform, DrawStyle::default().with_color(Color::BLUE));
+
+    // Draw a circle
+    let transform = Transform::new()
+        .with_position(0.6, 0.0, 0.0);
+    canvas.draw_circle(camera, transform, 0.3, DrawStyle::default().with_color(Color::GREEN));
+
+    let frame = renderer.render(&canvas).expect("Unable to render frame");
+    frame.write_to_file("hello_headless.png");
+}
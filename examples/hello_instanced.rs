@@ -0,0 +1,66 @@
+use rgfx::prelude::*;
+
+const PARTICLES: usize = 5000;
+
+#[derive(Debug)]
+pub struct MyWindow {
+    camera: Camera,
+    particles: Vec<(Vector2<f32>, Vector2<f32>, Color)>,
+}
+
+impl Default for MyWindow {
+    fn default() -> Self {
+        let particles = (0..PARTICLES)
+            .map(|_| {
+                let velocity = Vector2::new(rand::random::<f32>() - 0.5, rand::random::<f32>() - 0.5);
+                (Vector2::new(0.0, 0.0), velocity, Color::random())
+            })
+            .collect();
+
+        Self {
+            camera: Camera::default_orthographic(800.0, 600.0),
+            particles,
+        }
+    }
+}
+
+impl AppHandler for MyWindow {
+
+    fn on_event(&mut self, event: AppEvent) {
+        if let AppEvent::WindowResized(size) = event {
+            self.camera.update_viewport(size.x as f32, size.y as f32);
+        }
+    }
+
+    fn on_update(&mut self, delta: f32) {
+        for (position, velocity, _) in self.particles.iter_mut() {
+            *position += *velocity * delta;
+
+            // Bounce off the edges of the view
+            if position.x.abs() > 1.3 { velocity.x = -velocity.x; }
+            if position.y.abs() > 1.0 { velocity.y = -velocity.y; }
+        }
+    }
+
+    fn on_draw(&mut self, canvas: &mut Canvas) {
+
+        canvas.clear(Color::BLACK);
+
+        let instances: Vec<InstanceData> = self.particles.iter()
+            .map(|(position, _, color)| {
+                InstanceData::new(Transform::new().with_position(position.x, position.y, 0.0))
+                    .with_tint(*color)
+            })
+            .collect();
+
+        let mesh = MeshBuilder2D::from_rectangle(0.01, 0.01, Color::WHITE.into());
+        canvas.draw_instanced(&mut self.camera, mesh, &instances, DrawStyle::default());
+
+    }
+
+}
+
+fn main() {
+    Window::new(800, 600, "Hello Instanced", Box::new(MyWindow::default()))
+        .run();
+}
@@ -47,17 +47,17 @@ impl AppHandler for MyWindow {
                 }
 
             }
-            AppEvent::KeyReleased(key) => {
+            AppEvent::KeyReleased(_key) => {
                 //println!("Key released: {:?}", key);
             }
             AppEvent::CursorMoved(x, y) => {
                 // Update mouse position
                 self.mouse_pos = Point2::new(x as f32, y as f32);
             }
-            AppEvent::MouseButtonPressed(button) => {
+            AppEvent::MouseButtonPressed(_button) => {
                 //println!("Mouse button pressed: {}", button);
             }
-            AppEvent::MouseButtonReleased(button) => {
+            AppEvent::MouseButtonReleased(_button) => {
                 //println!("Mouse button released: {}", button);
             }
         }
@@ -0,0 +1,65 @@
+use rgfx::prelude::*;
+
+#[derive(Debug)]
+pub struct MyWindow {
+    camera: Camera,
+    font: Font,
+    elapsed: f32,
+}
+
+impl Default for MyWindow {
+    fn default() -> Self {
+        Self {
+            camera: Camera::default_orthographic(800.0, 600.0),
+            font: Font::from_file("examples/assets/DejaVuSans.ttf").expect("Unable to load font"),
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl AppHandler for MyWindow {
+
+    fn on_event(&mut self, event: AppEvent) {
+        if let AppEvent::WindowResized(size) = event {
+            self.camera.update_viewport(size.x as f32, size.y as f32);
+        }
+    }
+
+    fn on_update(&mut self, delta: f32) {
+        self.elapsed += delta;
+    }
+
+    fn on_draw(&mut self, canvas: &mut Canvas) {
+
+        canvas.clear(Color::WHITE);
+
+        let camera = &mut self.camera;
+
+        // Draw a title centered at the top
+        let transform = Transform::new().with_position(0.0, 0.9, 0.0);
+        let style = DrawStyle::new(Color::BLACK).with_text_align(TextAlign::Center);
+        canvas.draw_text(camera, transform, "Hello, rgfx!", &self.font, 0.2, style);
+
+        // Draw a box sized to fit some multi-line text
+        let text = "Kerning: AVAWAY\nLine breaks\nand alignment";
+        let size = self.font.measure(text, 0.1);
+        let transform = Transform::new().with_position(0.0, 0.1 - size.y / 2.0, 0.0);
+        canvas.draw_rectangle(camera, transform, size.x + 0.1, size.y + 0.1, DrawStyle::new(Color::LIGHT_GRAY));
+        let transform = Transform::new().with_position(-size.x / 2.0, 0.1, 0.0);
+        canvas.draw_text(camera, transform, text, &self.font, 0.1, DrawStyle::new(Color::BLUE));
+
+        // Draw rotating text
+        let transform = Transform::new()
+            .with_position(0.0, -0.6, 0.0)
+            .with_rotation(0.0, 0.0, self.elapsed * 45.0);
+        let style = DrawStyle::new(Color::RED).with_text_align(TextAlign::Center);
+        canvas.draw_text(camera, transform, "spin", &self.font, 0.15, style);
+
+    }
+
+}
+
+fn main() {
+    Window::new(800, 600, "Hello Text", Box::new(MyWindow::default()))
+        .run();
+}
@@ -48,6 +48,7 @@ pub struct Window {
     elapsed_since_last_frame: f32,
     start: std::time::Instant,
     target_frame_time: f32,
+    shader_hot_reload: bool,
 }
 
 impl Window {
@@ -57,7 +58,7 @@ impl Window {
             .with_title(title)
             .with_inner_size(winit::dpi::PhysicalSize::new(width, height));
 
-        let draw_commander = Canvas::default();
+        let draw_commander = Canvas::new(width as f32, height as f32);
 
         Self {
             window_attributes,
@@ -68,6 +69,7 @@ impl Window {
             elapsed_since_last_frame: 0.0,
             start: std::time::Instant::now(),
             target_frame_time: 1.0 / 60.0, // default to 60 FPS
+            shader_hot_reload: false,
         }
     }
 
@@ -83,6 +85,15 @@ impl Window {
         self
     }
 
+    /// Reloads material and post effect shaders loaded from files whenever the files change, for use during development.
+    pub fn set_shader_hot_reload(&mut self, enabled: bool) -> &mut Self {
+        self.shader_hot_reload = enabled;
+        if let Some(display) = &mut self.display {
+            display.set_shader_hot_reload(enabled);
+        }
+        self
+    }
+
 }
 
 impl ApplicationHandler for Window {
@@ -98,9 +109,10 @@ impl ApplicationHandler for Window {
         };
 
 
-        let display = RenderState::new(window.clone());
+        let mut display = RenderState::new(window.clone());
+        display.set_shader_hot_reload(self.shader_hot_reload);
 
-        //println!("Adaptor: {:?}", display.get_adaptor_info());
+        debug!("Adapter: {:?}", display.adapter_info());
 
         self.display = Some(display);
         self.winit_window = Some(window.clone());
@@ -0,0 +1,44 @@
+use wgpu::{BlendComponent, BlendFactor, BlendOperation, BlendState};
+
+/// How the color of a draw is combined with what is already on the canvas.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Overwrites the destination, ignoring alpha.
+    None,
+    /// Standard alpha blending for straight (non-premultiplied) alpha.
+    #[default]
+    Alpha,
+    /// Alpha blending for colors already multiplied by their alpha.
+    PremultipliedAlpha,
+    /// Adds the source color weighted by its alpha, useful for glows and particles.
+    Additive,
+    /// Multiplies the source and destination colors, darkening the result.
+    Multiply,
+    /// Inverse of multiply, lightening the result.
+    Screen,
+}
+
+impl From<BlendMode> for BlendState {
+    fn from(mode: BlendMode) -> Self {
+        let component = |src_factor, dst_factor| BlendComponent { src_factor, dst_factor, operation: BlendOperation::Add };
+        let alpha_over = component(BlendFactor::One, BlendFactor::OneMinusSrcAlpha);
+
+        match mode {
+            BlendMode::None => BlendState::REPLACE,
+            BlendMode::Alpha => BlendState::ALPHA_BLENDING,
+            BlendMode::PremultipliedAlpha => BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            BlendMode::Additive => BlendState {
+                color: component(BlendFactor::SrcAlpha, BlendFactor::One),
+                alpha: component(BlendFactor::One, BlendFactor::One),
+            },
+            BlendMode::Multiply => BlendState {
+                color: component(BlendFactor::Dst, BlendFactor::Zero),
+                alpha: alpha_over,
+            },
+            BlendMode::Screen => BlendState {
+                color: component(BlendFactor::One, BlendFactor::OneMinusSrc),
+                alpha: alpha_over,
+            },
+        }
+    }
+}
@@ -1,5 +1,13 @@
 use cgmath::{ortho, perspective, Deg, InnerSpace, Matrix4, Point3, SquareMatrix, Vector3};
 
+/// Maps the -1 to 1 depth range of cgmath's projections onto the 0 to 1 range wgpu clips to.
+const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
 pub trait CameraMatrix {
     fn to_view_projection_matrix(&self) -> Matrix4<f32>;
 }
@@ -46,6 +54,18 @@ pub struct Camera {
 
 impl Camera {
 
+    pub fn new(position: Point3<f32>, target: Point3<f32>, up: Vector3<f32>, projection: Projection) -> Self {
+        let mut camera = Self {
+            position,
+            target,
+            up,
+            projection,
+            view_projection_matrix: Matrix4::identity()
+        };
+        camera.update_view_projection_matrix();
+        camera
+    }
+
     pub fn default_perspective(width: f32, height: f32) -> Self {
         Self {
             position: Point3::new(0.0, 0.0, 5.0),
@@ -86,13 +106,13 @@ impl Camera {
     }
 
     fn projection_matrix(&self) -> Matrix4<f32> {
-        match self.projection {
-            Projection::Perspective { fov_y, near, far, scale, aspect } => {
+        OPENGL_TO_WGPU_MATRIX * match self.projection {
+            Projection::Perspective { fov_y, near, far, aspect, .. } => {
                 perspective(fov_y, aspect, near, far)
             }
-            Projection::Orthographic { left, right, bottom, top, near, far, scale, aspect } => {
+            Projection::Orthographic { bottom, top, near, far, aspect, .. } => {
 
-                let half_h = 1.0;
+                let half_h = (top - bottom) / 2.0;
                 let half_w = half_h * aspect;
 
                 ortho(
@@ -163,3 +183,33 @@ impl CameraMatrix for Camera {
         self.view_projection_matrix
     }
 }
+
+/// A camera for drawing in screen space, with the origin at the top-left corner, y pointing down and one unit per pixel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScreenCamera {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ScreenCamera {
+
+    pub fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+
+}
+
+impl CameraMatrix for ScreenCamera {
+    fn to_view_projection_matrix(&self) -> Matrix4<f32> {
+        let width = self.width.max(1.0);
+        let height = self.height.max(1.0);
+
+        // Keep the depth of every vertex in the middle of the clip range
+        Matrix4::new(
+            2.0 / width, 0.0, 0.0, 0.0,
+            0.0, -2.0 / height, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+            -1.0, 1.0, 0.5, 1.0,
+        )
+    }
+}
@@ -1,15 +1,40 @@
-use crate::graphics::camera::CameraMatrix;
+use crate::graphics::blend::BlendMode;
+use crate::graphics::camera::{CameraMatrix, ScreenCamera};
 use crate::graphics::color::Color;
+use crate::graphics::font::{Font, TextAlign};
+use crate::graphics::gltf::GltfModel;
 use crate::graphics::image::Image;
-use crate::graphics::transform::Transform;
+use crate::graphics::light::Lighting;
+use crate::graphics::material::{Material, Material3D};
+use crate::graphics::post_effect::PostEffect;
+use crate::graphics::render_target::RenderTarget;
+use crate::graphics::stroke::Stroke;
+use crate::graphics::transform::{ModelMatrix, Transform};
 use crate::renderer::mesh::{Mesh, MeshBuilder2D};
 use crate::renderer::state::DrawCommand;
-use log::trace;
+use crate::renderer::tessellation::{self, TriangleBuilder};
+use crate::renderer::vertex::{InstanceData, Vertex2D, Vertex3D};
+use cgmath::{InnerSpace, Matrix4, Point2, SquareMatrix, Vector2, Vector4};
+use log::{trace, warn};
+use std::f32::consts::TAU;
+
+/// Maximum distance in pixels between a curve and the segments approximating it.
+const CURVE_TOLERANCE: f32 = 0.25;
+const MIN_CIRCLE_SEGMENTS: f32 = 8.0;
+const MAX_CIRCLE_SEGMENTS: f32 = 256.0;
+/// Segments used for a full circle when the canvas size is unknown.
+const DEFAULT_CIRCLE_SEGMENTS: f32 = 32.0;
 
 #[derive(Clone, Debug, Default)]
 pub struct DrawStyle {
     pub color: Color,
-    pub image: Option<Image>
+    pub image: Option<Image>,
+    pub blend_mode: BlendMode,
+    pub text_align: TextAlign,
+    /// Draws shapes as outlines with this stroke instead of filling them, also used for lines.
+    pub stroke: Option<Stroke>,
+    /// Draws meshes and shapes with a custom shader instead of the built-in one.
+    pub material: Option<Material>
 }
 
 impl DrawStyle {
@@ -17,7 +42,11 @@ impl DrawStyle {
     pub fn new(color: Color) -> Self {
         Self {
             color,
-            image: None
+            image: None,
+            blend_mode: BlendMode::default(),
+            text_align: TextAlign::default(),
+            stroke: None,
+            material: None
         }
     }
 
@@ -30,11 +59,80 @@ impl DrawStyle {
         self.color = color;
         self
     }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn with_text_align(mut self, text_align: TextAlign) -> Self {
+        self.text_align = text_align;
+        self
+    }
+
+    pub fn with_stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = Some(stroke);
+        self
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = Some(material);
+        self
+    }
+}
+
+/// A rectangle in screen pixels, with the origin at the top-left corner of the canvas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClipRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ClipRect {
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Returns the area covered by both rectangles, which is empty if they do not overlap.
+    pub fn intersect(&self, other: &ClipRect) -> ClipRect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        ClipRect::new(x, y, (right - x).max(0.0), (bottom - y).max(0.0))
+    }
+
+    /// Returns the whole pixels covered by the rectangle within a target of the given size as x, y, width and height,
+    /// or `None` if no pixels are covered.
+    pub(crate) fn to_pixels(self, target_width: u32, target_height: u32) -> Option<[u32; 4]> {
+        let x = self.x.floor().clamp(0.0, target_width as f32) as u32;
+        let y = self.y.floor().clamp(0.0, target_height as f32) as u32;
+        let right = (self.x + self.width).ceil().clamp(0.0, target_width as f32) as u32;
+        let bottom = (self.y + self.height).ceil().clamp(0.0, target_height as f32) as u32;
+        (right > x && bottom > y).then_some([x, y, right - x, bottom - y])
+    }
+
+}
+
+/// Which side of a mask's mesh stays visible.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MaskMode {
+    #[default]
+    Inside,
+    Outside,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct Canvas {
     draw_commands: Vec<DrawCommand>,
+    transform_stack: Vec<Matrix4<f32>>,
+    clip_stack: Vec<ClipRect>,
+    mask_depth: usize,
+    post_effects: Vec<PostEffect>,
+    lighting: Option<Lighting>,
     pub(crate) bg_color: Color,
     pub width: f32,
     pub height: f32,
@@ -42,37 +140,436 @@ pub struct Canvas {
 
 impl Canvas {
 
+    /// Creates an empty canvas with the given size in pixels.
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a camera for drawing in pixels over the whole canvas, with the origin at the top-left corner and y pointing down.
+    /// Images and text drawn with it stay upright.
+    pub fn screen_camera(&self) -> ScreenCamera {
+        ScreenCamera::new(self.width, self.height)
+    }
+
     pub fn clear(&mut self, bg_color: Color) {
+        if !self.transform_stack.is_empty() {
+            warn!("{} transforms were pushed but not popped last frame", self.transform_stack.len());
+            self.transform_stack.clear();
+        }
+        if !self.clip_stack.is_empty() {
+            warn!("{} clip rectangles were pushed but not popped last frame", self.clip_stack.len());
+            self.clip_stack.clear();
+        }
+        if self.mask_depth > 0 {
+            warn!("{} masks were pushed but not popped last frame", self.mask_depth);
+            self.mask_depth = 0;
+        }
         self.draw_commands.clear();
         self.bg_color = bg_color;
         trace!("Starting new frame");
     }
 
+    /// Adds an effect applied to the whole frame after everything is drawn, after any effects already added.
+    /// Effects stay in place across frames until they are cleared.
+    pub fn add_post_effect(&mut self, effect: PostEffect) {
+        self.post_effects.push(effect);
+    }
+
+    /// Removes all post effects.
+    pub fn clear_post_effects(&mut self) {
+        self.post_effects.clear();
+    }
+
+    /// Returns the post effects in the order they are applied.
+    pub fn post_effects(&self) -> &[PostEffect] {
+        &self.post_effects
+    }
+
+    /// Shades the 3D meshes drawn from now on with the lighting, according to their material's shading.
+    /// The lighting stays in place across frames until it is replaced or cleared.
+    pub fn set_lighting(&mut self, lighting: Lighting) {
+        self.lighting = Some(lighting);
+    }
+
+    /// Draws the 3D meshes drawn from now on unlit.
+    pub fn clear_lighting(&mut self) {
+        self.lighting = None;
+    }
+
+    pub fn lighting(&self) -> Option<&Lighting> {
+        self.lighting.as_ref()
+    }
+
+    /// Makes the transform, combined with any already pushed, the parent of everything drawn until it is popped.
+    pub fn push_transform(&mut self, transform: Transform) {
+        let matrix = self.model_matrix(&transform);
+        self.transform_stack.push(matrix);
+    }
+
+    /// Removes the most recently pushed transform.
+    pub fn pop_transform(&mut self) {
+        if self.transform_stack.pop().is_none() {
+            warn!("Popped a transform without a matching push");
+        }
+    }
+
+    /// Draws everything in the closure relative to the transform.
+    pub fn with_transform<F: FnOnce(&mut Canvas)>(&mut self, transform: Transform, draw: F) {
+        self.push_transform(transform);
+        draw(self);
+        self.pop_transform();
+    }
+
+    /// Clips everything drawn until the matching pop to a rectangle in screen pixels, with the origin at the top-left corner.
+    /// Nested clip rectangles are intersected with the ones already pushed.
+    pub fn push_clip_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let mut rect = ClipRect::new(x, y, width, height);
+        if let Some(parent) = self.clip_stack.last() {
+            rect = rect.intersect(parent);
+        }
+        self.clip_stack.push(rect);
+        self.draw_commands.push(DrawCommand::SetClipRect(Some(rect)));
+    }
+
+    /// Clips everything drawn until the matching pop to the screen area covered by a rectangle drawn with the same camera,
+    /// transform and size. Rotated rectangles clip to their screen-aligned bounds.
+    /// Rectangles reaching the camera plane or behind it have no bounds on screen and clip everything away.
+    pub fn push_world_clip_rect(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, width: f32, height: f32) {
+        let mvp = camera.to_view_projection_matrix() * self.model_matrix(&transform);
+        let (half_width, half_height) = (width / 2.0, height / 2.0);
+        let corners = [(-half_width, -half_height), (half_width, -half_height), (half_width, half_height), (-half_width, half_height)];
+
+        let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+        for (x, y) in corners {
+            let clip = mvp * Vector4::new(x, y, 1.0, 1.0);
+            if clip.w < f32::EPSILON {
+                warn!("World clip rectangle reaches behind the camera, clipping everything");
+                self.push_clip_rect(0.0, 0.0, 0.0, 0.0);
+                return;
+            }
+            let screen = [
+                (clip.x / clip.w + 1.0) / 2.0 * self.width,
+                (1.0 - clip.y / clip.w) / 2.0 * self.height,
+            ];
+            min = [min[0].min(screen[0]), min[1].min(screen[1])];
+            max = [max[0].max(screen[0]), max[1].max(screen[1])];
+        }
+
+        self.push_clip_rect(min[0], min[1], max[0] - min[0], max[1] - min[1]);
+    }
+
+    /// Removes the most recently pushed clip rectangle.
+    pub fn pop_clip_rect(&mut self) {
+        if self.clip_stack.pop().is_none() {
+            warn!("Popped a clip rectangle without a matching push");
+            return;
+        }
+        self.draw_commands.push(DrawCommand::SetClipRect(self.clip_stack.last().copied()));
+    }
+
+    /// Masks everything drawn until the matching pop to the inside or outside of the mesh.
+    /// Nested masks are combined, so only areas passing every mask are drawn.
+    pub fn push_mask(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, mesh: Mesh<Vertex2D>, mode: MaskMode) {
+        self.mask_depth += 1;
+        self.draw_commands.push(DrawCommand::PushMask {
+            mesh,
+            camera_matrix: camera.to_view_projection_matrix().into(),
+            transform: self.model_matrix(&transform),
+            mode
+        });
+    }
+
+    /// Removes the most recently pushed mask.
+    pub fn pop_mask(&mut self) {
+        if self.mask_depth == 0 {
+            warn!("Popped a mask without a matching push");
+            return;
+        }
+        self.mask_depth -= 1;
+        self.draw_commands.push(DrawCommand::PopMask);
+    }
+
     /// Draws a triangle at the given transform with the given style.
     pub fn draw_triangle(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, style: DrawStyle) {
-        self.draw_mesh(camera, transform, MeshBuilder2D::from_triangle(style.color.into()), style);
+        let mesh = Self::shape_mesh(
+            &style,
+            || vec![vec![Vector2::new(0.0, 1.0), Vector2::new(1.0, -1.0), Vector2::new(-1.0, -1.0)]],
+            MeshBuilder2D::from_triangle,
+        );
+        self.draw_mesh(camera, transform, mesh, style);
     }
 
     /// Draws a rectangle at the given transform with the given style.
     pub fn draw_rectangle(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, width: f32, height: f32, style: DrawStyle) {
-        self.draw_mesh(camera, transform, MeshBuilder2D::from_rectangle(width, height, style.color.into()), style);
+        let (x, y) = (width / 2.0, height / 2.0);
+        let mesh = Self::shape_mesh(
+            &style,
+            || vec![vec![Vector2::new(-x, y), Vector2::new(x, y), Vector2::new(x, -y), Vector2::new(-x, -y)]],
+            |color| MeshBuilder2D::from_rectangle(width, height, color),
+        );
+        self.draw_mesh(camera, transform, mesh, style);
+    }
+
+    /// Draws a rectangle with rounded corners, with radii for the top-left, top-right, bottom-right and bottom-left corners.
+    pub fn draw_rounded_rectangle(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, width: f32, height: f32, radii: [f32; 4], style: DrawStyle) {
+        let radius = radii.iter().copied().fold(0.0, f32::max);
+        let segments = self.segments(camera, &transform, radius, 90.0);
+        let mesh = Self::shape_mesh(
+            &style,
+            || vec![tessellation::rounded_rectangle_points(width, height, radii, segments)],
+            |color| MeshBuilder2D::from_rounded_rectangle(width, height, radii, segments, color),
+        );
+        self.draw_mesh(camera, transform, mesh, style);
     }
 
     /// Draws a circle at the given transform with the given style.
     pub fn draw_circle(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, radius: f32, style: DrawStyle) {
-        self.draw_mesh(camera, transform, MeshBuilder2D::from_circle(radius, 32, style.color.into()), style);
+        self.draw_ellipse(camera, transform, radius, radius, style);
+    }
+
+    /// Draws an ellipse at the given transform with the given style.
+    pub fn draw_ellipse(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, radius_x: f32, radius_y: f32, style: DrawStyle) {
+        let segments = self.segments(camera, &transform, radius_x.max(radius_y), 360.0);
+        let mesh = Self::shape_mesh(
+            &style,
+            || vec![tessellation::ellipse_points(radius_x, radius_y, 0.0, TAU, segments)],
+            |color| MeshBuilder2D::from_ellipse(radius_x, radius_y, segments, color),
+        );
+        self.draw_mesh(camera, transform, mesh, style);
+    }
+
+    /// Draws a ring between two radii at the given transform with the given style.
+    pub fn draw_ring(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, inner_radius: f32, outer_radius: f32, style: DrawStyle) {
+        let segments = self.segments(camera, &transform, outer_radius, 360.0);
+        let mesh = Self::shape_mesh(
+            &style,
+            || [inner_radius, outer_radius].iter().map(|r| tessellation::ellipse_points(*r, *r, 0.0, TAU, segments)).collect(),
+            |color| MeshBuilder2D::from_ring(inner_radius, outer_radius, segments, color),
+        );
+        self.draw_mesh(camera, transform, mesh, style);
+    }
+
+    /// Draws a pie slice between two angles in degrees, counter-clockwise from the x axis.
+    pub fn draw_pie(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, radius: f32, start_angle: f32, end_angle: f32, style: DrawStyle) {
+        let segments = self.segments(camera, &transform, radius, end_angle - start_angle);
+        let mesh = Self::shape_mesh(
+            &style,
+            || {
+                let mut outline = tessellation::ellipse_points(radius, radius, start_angle.to_radians(), end_angle.to_radians(), segments);
+                outline.push(Vector2::new(0.0, 0.0));
+                vec![outline]
+            },
+            |color| MeshBuilder2D::from_pie(radius, start_angle, end_angle, segments, color),
+        );
+        self.draw_mesh(camera, transform, mesh, style);
+    }
+
+    /// Draws a circular arc between two angles in degrees using the style's stroke, or one a pixel wide.
+    pub fn draw_arc(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, radius: f32, start_angle: f32, end_angle: f32, style: DrawStyle) {
+        let segments = self.segments(camera, &transform, radius, end_angle - start_angle);
+        let stroke = self.stroke_or_hairline(camera, &transform, &style);
+        let mesh = MeshBuilder2D::from_arc(radius, start_angle, end_angle, segments, &stroke, style.color.into());
+        self.draw_mesh(camera, transform, mesh, style);
+    }
+
+    /// Draws a line between two points using the style's stroke, or one a pixel wide.
+    pub fn draw_line(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, start: Point2<f32>, end: Point2<f32>, style: DrawStyle) {
+        self.draw_polyline(camera, transform, &[start, end], false, style);
+    }
+
+    /// Draws connected line segments through the points using the style's stroke, or one a pixel wide, joining the last point to the first if closed.
+    pub fn draw_polyline(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, points: &[Point2<f32>], closed: bool, style: DrawStyle) {
+        let stroke = self.stroke_or_hairline(camera, &transform, &style);
+        let mesh = MeshBuilder2D::from_polyline(points, closed, &stroke, style.color.into());
+        self.draw_mesh(camera, transform, mesh, style);
     }
 
     /// Draws a mesh at the given transform with the given style.
-    pub fn draw_mesh(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, mesh: Mesh<crate::renderer::vertex::Vertex2D>, style: DrawStyle) {
+    pub fn draw_mesh(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, mut mesh: Mesh<Vertex2D>, style: DrawStyle) {
+        let camera_matrix = camera.to_view_projection_matrix();
+        if let Some(mirror) = mirroring(&camera_matrix) {
+            flip_uvs(&mut mesh, mirror);
+        }
+
         self.draw_commands.push(DrawCommand::DrawMesh2D {
+            mesh,
+            camera_matrix: camera_matrix.into(),
+            transform: self.model_matrix(&transform),
+            style
+        });
+    }
+
+    /// Draws a 3D mesh, hidden where other 3D meshes drawn this frame are closer to the camera.
+    /// Triangles are front facing when their vertices are in counter-clockwise order, and back faces are not drawn.
+    pub fn draw_mesh_3d(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, mesh: Mesh<Vertex3D>, material: Material3D) {
+        self.draw_commands.push(DrawCommand::DrawMesh3D {
             mesh,
             camera_matrix: camera.to_view_projection_matrix().into(),
+            transform: self.model_matrix(&transform),
+            material,
+            lighting: self.lighting.clone()
+        });
+    }
+
+    /// Draws every mesh of a glTF model's scene, placed by its nodes relative to the transform.
+    pub fn draw_gltf(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, model: &GltfModel) {
+        self.with_transform(transform, |canvas| {
+            for &root in &model.roots {
+                canvas.draw_gltf_node(camera, model, root, 0);
+            }
+        });
+    }
+
+    fn draw_gltf_node(&mut self, camera: &mut dyn CameraMatrix, model: &GltfModel, index: usize, depth: usize) {
+        // A valid hierarchy is a forest, so deeper nesting than there are nodes means a cycle
+        let Some(node) = model.nodes.get(index).filter(|_| depth < model.nodes.len()) else {
+            warn!("Skipping glTF node {}, it is missing or part of a cycle", index);
+            return;
+        };
+        self.with_transform(node.transform, |canvas| {
+            for primitive in node.mesh.and_then(|mesh| model.meshes.get(mesh)).iter().flat_map(|mesh| &mesh.primitives) {
+                canvas.draw_mesh_3d(camera, Transform::new(), primitive.mesh.clone(), primitive.material.clone());
+            }
+            for &child in &node.children {
+                canvas.draw_gltf_node(camera, model, child, depth + 1);
+            }
+        });
+    }
+
+    /// Draws the mesh once per instance with a single instanced draw call.
+    pub fn draw_instanced(&mut self, camera: &mut dyn CameraMatrix, mut mesh: Mesh<Vertex2D>, instances: &[InstanceData], style: DrawStyle) {
+        let camera_matrix = camera.to_view_projection_matrix();
+        if let Some(mirror) = mirroring(&camera_matrix) {
+            flip_uvs(&mut mesh, mirror);
+        }
+
+        // Instances are placed relative to the pushed transforms
+        let camera_matrix = camera_matrix * self.parent_matrix();
+
+        self.draw_commands.push(DrawCommand::DrawInstanced2D {
+            mesh,
+            camera_matrix: camera_matrix.into(),
+            instances: instances.to_vec(),
+            style
+        });
+    }
+
+    /// Draws text with its first line starting at the given transform.
+    /// The size is the height of a line in world units, lines run downwards and are aligned using the style's text alignment.
+    pub fn draw_text(&mut self, camera: &mut dyn CameraMatrix, transform: Transform, text: &str, font: &Font, size: f32, style: DrawStyle) {
+        let camera_matrix = camera.to_view_projection_matrix();
+        let mut transform = self.model_matrix(&transform);
+
+        // Lines keep running down the screen and glyphs stay upright
+        match mirroring(&camera_matrix) {
+            Some(Mirror::Horizontal) => transform = transform * Matrix4::from_nonuniform_scale(-1.0, 1.0, 1.0),
+            Some(Mirror::Vertical) => transform = transform * Matrix4::from_nonuniform_scale(1.0, -1.0, 1.0),
+            None => {}
+        }
+
+        // Rasterize glyphs at roughly their on-screen size so they stay crisp
+        let px = self.pixels_per_unit(camera_matrix, transform)
+            .map(|ppu| (ppu * size).round().clamp(4.0, 256.0) as u32)
+            .unwrap_or(32);
+
+        self.draw_commands.push(DrawCommand::DrawText {
+            glyphs: font.layout(text, size, px, style.text_align),
+            font: font.clone(),
+            camera_matrix: camera_matrix.into(),
             transform,
             style
         });
     }
 
+    /// Draws the canvas into the render target before this canvas is drawn, replacing the target's previous contents.
+    ///
+    /// The target's image can then be drawn on this canvas, or on later frames without rendering the target again.
+    pub fn render_to_target(&mut self, target: &RenderTarget, canvas: Canvas) {
+        let key = target.key();
+        if canvas.draw_commands.iter().any(|command| command.images().any(|image| image.path == key)) {
+            warn!("Render target cannot draw its own image, skipping");
+            return;
+        }
+        if !canvas.post_effects.is_empty() {
+            warn!("Post effects only apply to the frame, ignoring those of the render target's canvas");
+        }
+
+        self.draw_commands.push(DrawCommand::RenderToTarget {
+            target: target.clone(),
+            commands: canvas.draw_commands,
+            bg_color: canvas.bg_color,
+        });
+    }
+
+    /// Builds the filled shape, or strokes its closed outlines if the style has a stroke.
+    fn shape_mesh(style: &DrawStyle, outlines: impl FnOnce() -> Vec<Vec<Vector2<f32>>>, fill: impl FnOnce([f32; 4]) -> Mesh<Vertex2D>) -> Mesh<Vertex2D> {
+        match &style.stroke {
+            Some(stroke) => {
+                let mut builder = TriangleBuilder::new();
+                for outline in outlines() {
+                    tessellation::stroke_polyline(&mut builder, &outline, true, stroke);
+                }
+                builder.build(style.color.into())
+            }
+            None => fill(style.color.into()),
+        }
+    }
+
+    /// Picks how many segments approximate a curve so it stays within a fraction of a pixel of the true shape on screen.
+    fn segments(&self, camera: &mut dyn CameraMatrix, transform: &Transform, radius: f32, sweep_degrees: f32) -> usize {
+        let full_circle = match self.pixels_per_unit(camera.to_view_projection_matrix(), self.model_matrix(transform)) {
+            Some(ppu) if ppu * radius > 0.0 => {
+                let step = 2.0 * (1.0 - (CURVE_TOLERANCE / (ppu * radius)).min(1.0)).acos();
+                (TAU / step).ceil().clamp(MIN_CIRCLE_SEGMENTS, MAX_CIRCLE_SEGMENTS)
+            }
+            _ => DEFAULT_CIRCLE_SEGMENTS,
+        };
+        let fraction = (sweep_degrees.abs() / 360.0).min(1.0);
+        (full_circle * fraction).ceil().max(1.0) as usize
+    }
+
+    /// Returns the style's stroke, or a stroke one pixel wide on screen when it has none.
+    fn stroke_or_hairline(&self, camera: &mut dyn CameraMatrix, transform: &Transform, style: &DrawStyle) -> Stroke {
+        style.stroke.unwrap_or_else(|| {
+            let width = self.pixels_per_unit(camera.to_view_projection_matrix(), self.model_matrix(transform))
+                .filter(|ppu| *ppu > 0.0)
+                .map_or(1.0, |ppu| 1.0 / ppu);
+            Stroke::new(width)
+        })
+    }
+
+    /// Returns the combination of all pushed transforms.
+    fn parent_matrix(&self) -> Matrix4<f32> {
+        self.transform_stack.last().copied().unwrap_or_else(Matrix4::identity)
+    }
+
+    /// Returns the model matrix of the transform relative to the pushed transforms.
+    fn model_matrix(&self, transform: &Transform) -> Matrix4<f32> {
+        self.parent_matrix() * transform.to_matrix()
+    }
+
+    /// Estimates how many pixels one local unit covers on screen, or `None` if the canvas has no size.
+    fn pixels_per_unit(&self, camera_matrix: Matrix4<f32>, model_matrix: Matrix4<f32>) -> Option<f32> {
+        if self.height <= 0.0 {
+            return None;
+        }
+
+        let mvp = camera_matrix * model_matrix;
+        let origin = mvp * Vector4::new(0.0, 0.0, 1.0, 1.0);
+        let up = mvp * Vector4::new(0.0, 1.0, 1.0, 1.0);
+        if origin.w.abs() < f32::EPSILON || up.w.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let dx = (up.x / up.w - origin.x / origin.w) * self.width / 2.0;
+        let dy = (up.y / up.w - origin.y / origin.w) * self.height / 2.0;
+        Some((dx * dx + dy * dy).sqrt())
+    }
+
     pub fn to_frame(&self) -> &[DrawCommand] {
         trace!("Getting frame with {} draw commands", self.draw_commands.len());
         self.draw_commands.as_slice()
@@ -80,4 +577,32 @@ impl Canvas {
 
 }
 
+/// The screen axis along which a camera mirrors what it shows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mirror {
+    Horizontal,
+    Vertical,
+}
+
+/// Returns how the camera mirrors the image on screen, as screen space does vertically, or `None` if it does not.
+fn mirroring(camera_matrix: &Matrix4<f32>) -> Option<Mirror> {
+    if camera_matrix.x.x * camera_matrix.y.y - camera_matrix.y.x * camera_matrix.x.y >= 0.0 {
+        return None;
+    }
 
+    // The mirrored axis is the one pointing most against its own direction on screen
+    let x = camera_matrix.x.x / Vector2::new(camera_matrix.x.x, camera_matrix.x.y).magnitude();
+    let y = camera_matrix.y.y / Vector2::new(camera_matrix.y.x, camera_matrix.y.y).magnitude();
+    Some(if x < y { Mirror::Horizontal } else { Mirror::Vertical })
+}
+
+/// Flips the texture coordinates along the mirrored axis so images stay the right way round.
+fn flip_uvs(mesh: &mut Mesh<Vertex2D>, mirror: Mirror) {
+    let axis = match mirror {
+        Mirror::Horizontal => 0,
+        Mirror::Vertical => 1,
+    };
+    for vertex in &mut mesh.vertices {
+        vertex.uv[axis] = 1.0 - vertex.uv[axis];
+    }
+}
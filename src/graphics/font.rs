@@ -0,0 +1,161 @@
+use ab_glyph::{Font as _, FontArc, GlyphId, PxScale, ScaleFont};
+use cgmath::Vector2;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static FONT_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Horizontal alignment of each line of text relative to the text's transform.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A TrueType or OpenType font used to draw text.
+#[derive(Clone, Debug)]
+pub struct Font {
+    id: u64,
+    font: FontArc,
+}
+
+/// Identifies a rasterized glyph in the glyph atlas.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font_id: u64,
+    pub glyph_id: u16,
+    pub px: u32,
+}
+
+/// A glyph placed in text-local coordinates, with the rectangle its bitmap covers.
+#[derive(Copy, Clone, Debug)]
+pub struct GlyphQuad {
+    pub key: GlyphKey,
+    /// Minimum x, minimum y, maximum x and maximum y of the glyph, y pointing up.
+    pub rect: [f32; 4],
+}
+
+/// Coverage values of a rasterized glyph, one byte per pixel.
+pub(crate) struct GlyphBitmap {
+    pub width: u32,
+    pub height: u32,
+    pub coverage: Vec<u8>,
+}
+
+impl Font {
+
+    /// Loads a font from the bytes of a TTF or OTF file.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        let font = FontArc::try_from_vec(bytes).map_err(|e| format!("Failed to parse font: {}", e))?;
+        Ok(Self {
+            id: FONT_COUNTER.fetch_add(1, Ordering::Relaxed),
+            font,
+        })
+    }
+
+    /// Loads a font from a TTF or OTF file.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read font file {}: {}", path, e))?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Returns the distance between the baselines of two lines of text.
+    pub fn line_height(&self, size: f32) -> f32 {
+        let scaled = self.font.as_scaled(PxScale::from(size));
+        scaled.height() + scaled.line_gap()
+    }
+
+    /// Measures the width and height of the text when drawn at the given size.
+    pub fn measure(&self, text: &str, size: f32) -> Vector2<f32> {
+        let scaled = self.font.as_scaled(PxScale::from(size));
+        let width = text.split('\n')
+            .map(|line| self.line_width(line, size))
+            .fold(0.0, f32::max);
+        let lines = text.split('\n').count() as f32;
+        Vector2::new(width, (lines - 1.0) * self.line_height(size) + scaled.height())
+    }
+
+    /// Lays out the text and returns a quad per visible glyph, rasterized at `px` pixels per line.
+    ///
+    /// The origin is at the top of the first line, lines run downwards and are aligned horizontally around x = 0.
+    pub(crate) fn layout(&self, text: &str, size: f32, px: u32, align: TextAlign) -> Vec<GlyphQuad> {
+        let scaled = self.font.as_scaled(PxScale::from(size));
+        let raster_scale = size / px as f32;
+        let mut quads = Vec::new();
+
+        for (line_index, line) in text.split('\n').enumerate() {
+            let width = self.line_width(line, size);
+            let mut x = match align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => -width / 2.0,
+                TextAlign::Right => -width,
+            };
+            let baseline = -scaled.ascent() - line_index as f32 * self.line_height(size);
+            let mut previous: Option<GlyphId> = None;
+
+            for c in line.chars() {
+                let id = scaled.glyph_id(c);
+                if let Some(previous) = previous {
+                    x += scaled.kern(previous, id);
+                }
+                previous = Some(id);
+
+                let glyph = id.with_scale_and_position(PxScale::from(px as f32), ab_glyph::point(0.0, 0.0));
+                if let Some(outlined) = self.font.outline_glyph(glyph) {
+                    // Bitmap bounds are in pixels with y pointing down from the baseline
+                    let bounds = outlined.px_bounds();
+                    quads.push(GlyphQuad {
+                        key: GlyphKey { font_id: self.id, glyph_id: id.0, px },
+                        rect: [
+                            x + bounds.min.x * raster_scale,
+                            baseline - bounds.max.y * raster_scale,
+                            x + bounds.max.x * raster_scale,
+                            baseline - bounds.min.y * raster_scale,
+                        ],
+                    });
+                }
+
+                x += scaled.h_advance(id);
+            }
+        }
+
+        quads
+    }
+
+    /// Rasterizes the glyph at the size stored in the key.
+    pub(crate) fn rasterize(&self, key: GlyphKey) -> Option<GlyphBitmap> {
+        let glyph = GlyphId(key.glyph_id).with_scale_and_position(PxScale::from(key.px as f32), ab_glyph::point(0.0, 0.0));
+        let outlined = self.font.outline_glyph(glyph)?;
+        let bounds = outlined.px_bounds();
+        let width = bounds.width() as u32;
+        let height = bounds.height() as u32;
+        let mut coverage = vec![0u8; (width * height) as usize];
+
+        outlined.draw(|x, y, c| {
+            if x < width && y < height {
+                coverage[(y * width + x) as usize] = (c.clamp(0.0, 1.0) * 255.0) as u8;
+            }
+        });
+
+        Some(GlyphBitmap { width, height, coverage })
+    }
+
+    fn line_width(&self, line: &str, size: f32) -> f32 {
+        let scaled = self.font.as_scaled(PxScale::from(size));
+        let mut width = 0.0;
+        let mut previous: Option<GlyphId> = None;
+
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                width += scaled.kern(previous, id);
+            }
+            width += scaled.h_advance(id);
+            previous = Some(id);
+        }
+
+        width
+    }
+
+}
@@ -0,0 +1,283 @@
+use crate::graphics::camera::{Camera, Projection};
+use crate::graphics::color::Color;
+use crate::graphics::image::Image;
+use crate::graphics::material::{Material3D, Shading};
+use crate::graphics::transform::{ModelMatrix, Transform};
+use crate::renderer::mesh::Mesh;
+use crate::renderer::vertex::Vertex3D;
+use ::gltf::camera::Projection as GltfProjection;
+use ::gltf::image::Format;
+use ::gltf::mesh::{Mode, Semantic};
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Quaternion, Rad, SquareMatrix, Vector3, Vector4};
+use image::{DynamicImage, GrayAlphaImage, GrayImage, RgbImage, RgbaImage};
+use log::warn;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Error returned when a glTF file cannot be read or is not valid glTF 2.0.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GltfError {
+    /// The file, or a buffer or image it refers to, could not be read.
+    Io { path: String, message: String },
+    /// The file is malformed or uses data this importer cannot read.
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Io { path, message } => write!(f, "Failed to read {}: {}", path, message),
+            GltfError::Invalid { path, message } => write!(f, "Invalid glTF file {}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+/// A part of a glTF mesh drawn with one material.
+#[derive(Clone, Debug)]
+pub struct GltfPrimitive {
+    pub mesh: Mesh<Vertex3D>,
+    pub material: Material3D,
+}
+
+#[derive(Clone, Debug)]
+pub struct GltfMesh {
+    pub name: String,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+/// A node of the scene, placed relative to its parent.
+#[derive(Clone, Debug)]
+pub struct GltfNode {
+    pub name: String,
+    pub transform: Transform,
+    /// Index into the model's meshes.
+    pub mesh: Option<usize>,
+    /// Indices into the model's nodes.
+    pub children: Vec<usize>,
+}
+
+/// The meshes, node hierarchy and cameras of a glTF 2.0 file.
+#[derive(Clone, Debug)]
+pub struct GltfModel {
+    pub meshes: Vec<GltfMesh>,
+    pub nodes: Vec<GltfNode>,
+    /// Nodes at the top of the scene, drawn by [`Canvas::draw_gltf`](crate::prelude::Canvas::draw_gltf).
+    pub roots: Vec<usize>,
+    /// Cameras of the scene, placed where their nodes put them.
+    pub cameras: Vec<Camera>,
+}
+
+impl GltfModel {
+
+    /// Loads a `.gltf` or `.glb` file, with buffers and images embedded in it or in local files next to it.
+    ///
+    /// Only the default scene, or the first if there is no default, is placed and searched for cameras.
+    /// Materials take the base color, base color texture and metallic and roughness factors, and primitives that are not triangles are skipped.
+    pub fn from_file(path: &str) -> Result<Self, GltfError> {
+        let (document, buffers, images) = ::gltf::import(path).map_err(|e| match e {
+            ::gltf::Error::Io(e) => GltfError::Io { path: path.to_string(), message: e.to_string() },
+            e => GltfError::Invalid { path: path.to_string(), message: e.to_string() },
+        })?;
+
+        let images: Vec<_> = images.into_iter().enumerate()
+            .map(|(index, data)| convert_image(data, format!("{}#image{}", path, index)))
+            .collect();
+
+        let mut meshes = Vec::new();
+        for mesh in document.meshes() {
+            let mut primitives = Vec::new();
+            for primitive in mesh.primitives() {
+                let Some(triangles) = read_triangles(&primitive, &buffers, path)? else {
+                    warn!("Skipping {:?} primitive of mesh {} in {}, only triangles are supported", primitive.mode(), mesh.index(), path);
+                    continue;
+                };
+                let material = read_material(&primitive.material(), &images);
+                primitives.extend(split_for_u16_indices(triangles).into_iter().map(|mesh| GltfPrimitive { mesh, material: material.clone() }));
+            }
+            meshes.push(GltfMesh { name: mesh.name().unwrap_or_default().to_string(), primitives });
+        }
+
+        let nodes = document.nodes()
+            .map(|node| GltfNode {
+                name: node.name().unwrap_or_default().to_string(),
+                transform: node_transform(&node),
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                children: node.children().map(|child| child.index()).collect(),
+            })
+            .collect();
+
+        let scene = document.default_scene().or_else(|| document.scenes().next());
+        let roots: Vec<_> = scene.iter().flat_map(|scene| scene.nodes()).collect();
+
+        let mut cameras = Vec::new();
+        let mut pending: Vec<_> = roots.iter().map(|node| (node.clone(), Matrix4::identity())).collect();
+        while let Some((node, parent)) = pending.pop() {
+            let world = parent * node_transform(&node).to_matrix();
+            if let Some(camera) = node.camera() {
+                cameras.push(convert_camera(&camera, world));
+            }
+            pending.extend(node.children().map(|child| (child, world)));
+        }
+
+        let roots = roots.iter().map(|node| node.index()).collect();
+        Ok(Self { meshes, nodes, roots, cameras })
+    }
+
+}
+
+/// Vertices with 32-bit triangle indices, as read before being split into meshes.
+type Triangles = (Vec<Vertex3D>, Vec<u32>);
+
+/// Maps the node's translation, rotation and scale onto a transform, decomposing its matrix if it has one.
+fn node_transform(node: &::gltf::Node) -> Transform {
+    let (translation, [x, y, z, w], scale) = node.transform().decomposed();
+    Transform {
+        position: translation.into(),
+        rotation: Quaternion::new(w, x, y, z),
+        scale: scale.into(),
+        ..Transform::default()
+    }
+}
+
+/// Reads the vertices and triangle indices of a primitive, or `None` if it is made of points or lines.
+fn read_triangles(primitive: &::gltf::Primitive, buffers: &[::gltf::buffer::Data], path: &str) -> Result<Option<Triangles>, GltfError> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+    let Some(positions) = reader.read_positions() else {
+        return Err(GltfError::Invalid { path: path.to_string(), message: format!("Primitive {} has no positions", primitive.index()) });
+    };
+    let positions: Vec<[f32; 3]> = positions.collect();
+    let count = positions.len() as u32;
+    let indices: Vec<u32> = reader.read_indices().map(|indices| indices.into_u32().collect()).unwrap_or_else(|| (0..count).collect());
+    if let Some(index) = indices.iter().find(|&&i| i >= count) {
+        return Err(GltfError::Invalid { path: path.to_string(), message: format!("Primitive {} refers to vertex {} of {}", primitive.index(), index, count) });
+    }
+
+    let indices = match primitive.mode() {
+        Mode::Triangles => indices.chunks_exact(3).flatten().copied().collect(),
+        Mode::TriangleStrip => (2..indices.len())
+            .flat_map(|i| if i % 2 == 0 { [indices[i - 2], indices[i - 1], indices[i]] } else { [indices[i - 1], indices[i - 2], indices[i]] })
+            .collect(),
+        Mode::TriangleFan => (2..indices.len()).flat_map(|i| [indices[0], indices[i - 1], indices[i]]).collect(),
+        _ => return Ok(None),
+    };
+
+    let set = primitive.material().pbr_metallic_roughness().base_color_texture().map_or(0, |info| info.tex_coord());
+    let uvs: Vec<[f32; 2]> = reader.read_tex_coords(set).map(|uvs| uvs.into_f32().collect()).unwrap_or_default();
+    // Attributes are read per vertex, so each needs a value for every position
+    for (semantic, name) in [(Semantic::Normals, "NORMAL".to_string()), (Semantic::TexCoords(set), format!("TEXCOORD_{}", set))] {
+        if let Some(accessor) = primitive.get(&semantic) && accessor.count() != positions.len() {
+            return Err(GltfError::Invalid {
+                path: path.to_string(),
+                message: format!("Primitive {} has {} {} values for {} positions", primitive.index(), accessor.count(), name, positions.len()),
+            });
+        }
+    }
+    let vertex = |i: usize, normal: [f32; 3]| Vertex3D {
+        position: positions[i],
+        normal,
+        uv: uvs.get(i).copied().unwrap_or_default(),
+    };
+
+    match reader.read_normals() {
+        Some(normals) => {
+            let vertices = normals.enumerate().map(|(i, normal)| vertex(i, normal)).collect();
+            Ok(Some((vertices, indices)))
+        }
+        None => {
+            // Without normals glTF asks for flat shading, so every triangle gets vertices of its own
+            let mut vertices = Vec::with_capacity(indices.len());
+            for triangle in indices.chunks_exact(3) {
+                let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| Vector3::from(positions[i as usize]));
+                let normal = (b - a).cross(c - a);
+                let normal = if normal.magnitude2() > 0.0 { normal.normalize() } else { Vector3::unit_y() };
+                vertices.extend(triangle.iter().map(|&i| vertex(i as usize, normal.into())));
+            }
+            let indices = (0..vertices.len() as u32).collect();
+            Ok(Some((vertices, indices)))
+        }
+    }
+}
+
+fn read_material(material: &::gltf::Material, images: &[Option<Image>]) -> Material3D {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, a] = pbr.base_color_factor();
+    let image = pbr.base_color_texture().and_then(|info| images.get(info.texture().source().index()).cloned().flatten());
+    Material3D {
+        color: Color::new(r, g, b, a),
+        image,
+        shading: Shading::Pbr { metallic: pbr.metallic_factor(), roughness: pbr.roughness_factor() },
+    }
+}
+
+/// Splits triangles into meshes small enough for 16-bit indices.
+fn split_for_u16_indices((vertices, indices): Triangles) -> Vec<Mesh<Vertex3D>> {
+    if vertices.len() <= u16::MAX as usize + 1 {
+        return vec![Mesh { vertices, indices: indices.into_iter().map(|i| i as u16).collect() }];
+    }
+
+    let mut meshes = Vec::new();
+    let mut mesh = Mesh { vertices: Vec::new(), indices: Vec::new() };
+    let mut remapped = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        if mesh.vertices.len() + 3 > u16::MAX as usize + 1 {
+            meshes.push(std::mem::replace(&mut mesh, Mesh { vertices: Vec::new(), indices: Vec::new() }));
+            remapped.clear();
+        }
+        for &index in triangle {
+            let index = *remapped.entry(index).or_insert_with(|| {
+                mesh.vertices.push(vertices[index as usize]);
+                (mesh.vertices.len() - 1) as u16
+            });
+            mesh.indices.push(index);
+        }
+    }
+    meshes.push(mesh);
+    meshes
+}
+
+fn convert_image(data: ::gltf::image::Data, path: String) -> Option<Image> {
+    let (width, height, pixels) = (data.width, data.height, data.pixels);
+    let image = match data.format {
+        Format::R8G8B8A8 => RgbaImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8),
+        Format::R8G8B8 => RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8 => GrayAlphaImage::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8),
+        Format::R8 => GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+        format => {
+            warn!("Skipping image {}, {:?} pixels are not supported", path, format);
+            return None;
+        }
+    };
+    image.map(|image| Image { path, image })
+}
+
+/// Places a glTF camera, which looks down its node's negative z axis with y up.
+fn convert_camera(camera: &::gltf::Camera, world: Matrix4<f32>) -> Camera {
+    let position = Point3::from_vec((world * Vector4::unit_w()).truncate());
+    let forward = (world * -Vector4::unit_z()).truncate().normalize();
+    let up = (world * Vector4::unit_y()).truncate().normalize();
+
+    let projection = match camera.projection() {
+        GltfProjection::Perspective(perspective) => Projection::Perspective {
+            fov_y: Deg::from(Rad(perspective.yfov())),
+            near: perspective.znear(),
+            // Infinite projections are not supported, so stop far beyond the near plane instead
+            far: perspective.zfar().unwrap_or(perspective.znear() * 100_000.0),
+            scale: 1.0,
+            aspect: perspective.aspect_ratio().unwrap_or(1.0),
+        },
+        GltfProjection::Orthographic(orthographic) => Projection::Orthographic {
+            left: -orthographic.xmag(),
+            right: orthographic.xmag(),
+            bottom: -orthographic.ymag(),
+            top: orthographic.ymag(),
+            near: orthographic.znear(),
+            far: orthographic.zfar(),
+            scale: 1.0,
+            aspect: orthographic.xmag() / orthographic.ymag(),
+        },
+    };
+
+    Camera::new(position, position + forward, up, projection)
+}
@@ -0,0 +1,97 @@
+use crate::graphics::color::Color;
+use cgmath::{InnerSpace, Point3, Vector3};
+use log::warn;
+
+/// Maximum number of lights shading a 3D draw, beyond the ambient light.
+pub const MAX_LIGHTS: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightKind {
+    /// Light arriving from the same direction everywhere, like sunlight.
+    Directional { direction: Vector3<f32> },
+    /// Light shining in all directions from a point, fading out at the range.
+    Point { position: Point3<f32>, range: f32 },
+    /// Light shining from a point in a cone, full within the inner angle and fading out towards the outer angle, in degrees.
+    Spot { position: Point3<f32>, direction: Vector3<f32>, range: f32, inner_angle: f32, outer_angle: f32 },
+}
+
+/// A light shading 3D meshes, in world space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: Color,
+    /// Multiplies the color, and may go above 1 for bright lights.
+    pub intensity: f32,
+}
+
+impl Light {
+
+    /// Creates a light shining along the direction.
+    pub fn directional(direction: Vector3<f32>, color: Color) -> Self {
+        Self::new(LightKind::Directional { direction: direction.normalize() }, color)
+    }
+
+    pub fn point(position: Point3<f32>, range: f32, color: Color) -> Self {
+        Self::new(LightKind::Point { position, range }, color)
+    }
+
+    /// Creates a spot light with a cone of the given angle in degrees from its direction, softened over its outer fifth.
+    pub fn spot(position: Point3<f32>, direction: Vector3<f32>, range: f32, angle: f32, color: Color) -> Self {
+        Self::new(LightKind::Spot { position, direction: direction.normalize(), range, inner_angle: angle * 0.8, outer_angle: angle }, color)
+    }
+
+    fn new(kind: LightKind, color: Color) -> Self {
+        Self {
+            kind,
+            color,
+            intensity: 1.0,
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+}
+
+/// The lights shading 3D draws made while it is set on a canvas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lighting {
+    /// Light reaching every surface from all directions.
+    pub ambient: Color,
+    pub lights: Vec<Light>,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lighting {
+
+    /// Creates lighting with a dim gray ambient light and no other lights.
+    pub fn new() -> Self {
+        Self {
+            ambient: Color::new(0.1, 0.1, 0.1, 1.0),
+            lights: Vec::new(),
+        }
+    }
+
+    pub fn with_ambient(mut self, ambient: Color) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    /// Adds a light, ignoring it if there are already as many as the shader takes.
+    pub fn with_light(mut self, light: Light) -> Self {
+        if self.lights.len() < MAX_LIGHTS {
+            self.lights.push(light);
+        } else {
+            warn!("Lighting takes at most {} lights, ignoring {:?}", MAX_LIGHTS, light.kind);
+        }
+        self
+    }
+
+}
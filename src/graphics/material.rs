@@ -0,0 +1,170 @@
+use crate::graphics::color::Color;
+use crate::graphics::image::Image;
+use log::warn;
+use std::fmt;
+
+/// Maximum number of parameters a material passes to its shader.
+pub const MAX_MATERIAL_PARAMS: usize = 16;
+
+/// Maximum number of extra textures a material can sample.
+pub const MAX_MATERIAL_TEXTURES: usize = 4;
+
+/// Error returned when the shader of a material or post effect cannot be loaded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShaderError {
+    /// The shader file could not be read.
+    Io { path: String, message: String },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Io { path, message } => write!(f, "Failed to read shader file {}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Reads the WGSL source of a shader file.
+pub(crate) fn read_shader(path: &str) -> Result<String, ShaderError> {
+    std::fs::read_to_string(path).map_err(|e| ShaderError::Io { path: path.to_string(), message: e.to_string() })
+}
+
+/// A custom WGSL shader for drawing meshes and shapes, with parameters and textures of its own.
+///
+/// The shader is appended to a prelude declaring `VertexInput` and `VertexOutput` matching [`Vertex2D`](crate::prelude::Vertex2D),
+/// the camera and transform as `draw_uniform`, the style's image as `main_texture` and `main_sampler`, the parameters as
+/// `material.params`, four vectors of four, and the extra textures as `texture_0` to `texture_3` with `sampler_0` to `sampler_3`.
+/// The prelude's `default_vertex` and `base_color` do what the built-in shader does.
+/// The shader must define `fs_main` and may define `vs_main`, otherwise the built-in vertex stage is used.
+///
+/// Instanced draws and text ignore materials.
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub shader: String,
+    /// File the shader was loaded from, watched for changes when shader hot reload is enabled.
+    pub shader_path: Option<String>,
+    pub params: [f32; MAX_MATERIAL_PARAMS],
+    /// Extra images sampled by the shader, unused slots sample white.
+    pub textures: Vec<Image>,
+}
+
+impl Material {
+
+    /// Creates a material from the WGSL source of its shader.
+    pub fn new(shader: &str) -> Self {
+        Self {
+            shader: shader.to_string(),
+            shader_path: None,
+            params: [0.0; MAX_MATERIAL_PARAMS],
+            textures: Vec::new(),
+        }
+    }
+
+    /// Loads a material from a WGSL file.
+    pub fn from_file(path: &str) -> Result<Self, ShaderError> {
+        let shader = read_shader(path)?;
+        Ok(Self {
+            shader_path: Some(path.to_string()),
+            ..Self::new(&shader)
+        })
+    }
+
+    /// Sets the first parameters, leaving the rest at zero.
+    pub fn with_params(mut self, params: &[f32]) -> Self {
+        if params.len() > MAX_MATERIAL_PARAMS {
+            warn!("Materials take at most {} parameters, ignoring {}", MAX_MATERIAL_PARAMS, params.len() - MAX_MATERIAL_PARAMS);
+        }
+        let count = params.len().min(MAX_MATERIAL_PARAMS);
+        self.params = [0.0; MAX_MATERIAL_PARAMS];
+        self.params[..count].copy_from_slice(&params[..count]);
+        self
+    }
+
+    /// Adds an extra texture in the next free slot.
+    pub fn with_texture(mut self, image: Image) -> Self {
+        if self.textures.len() < MAX_MATERIAL_TEXTURES {
+            self.textures.push(image);
+        } else {
+            warn!("Materials take at most {} textures, ignoring {}", MAX_MATERIAL_TEXTURES, image.path);
+        }
+        self
+    }
+
+    /// Returns the keys the shader is compiled and cached under, its file or an empty string if it has none, then its source.
+    pub(crate) fn shader_key(&self) -> (&str, &str) {
+        (self.shader_path.as_deref().unwrap_or(""), &self.shader)
+    }
+
+}
+
+/// Materials are equal when they use the same shader, parameters and images, so draws using them can be batched.
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        self.shader_key() == other.shader_key()
+            && self.params == other.params
+            && self.textures.len() == other.textures.len()
+            && self.textures.iter().zip(&other.textures).all(|(a, b)| a.path == b.path)
+    }
+}
+
+/// How a 3D surface responds to the lights set on the canvas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shading {
+    /// Drawn in its color whatever the lighting.
+    Unlit,
+    /// Diffuse light with a highlight of the given strength, sharper as the shininess grows.
+    BlinnPhong { specular: f32, shininess: f32 },
+    /// Physically based metallic and roughness shading, both from 0 to 1.
+    Pbr { metallic: f32, roughness: f32 },
+}
+
+impl Default for Shading {
+    fn default() -> Self {
+        Shading::BlinnPhong { specular: 0.5, shininess: 32.0 }
+    }
+}
+
+/// Surface of a 3D mesh drawn with the built-in 3D shader.
+#[derive(Clone, Debug)]
+pub struct Material3D {
+    pub color: Color,
+    /// Image multiplied with the color, mapped using the mesh's texture coordinates.
+    pub image: Option<Image>,
+    /// Ignored while the canvas has no lighting set, so the mesh is drawn unlit.
+    pub shading: Shading,
+}
+
+impl Default for Material3D {
+    fn default() -> Self {
+        Self::new(Color::WHITE)
+    }
+}
+
+impl Material3D {
+
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            image: None,
+            shading: Shading::default(),
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_image(mut self, image: Image) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    pub fn with_shading(mut self, shading: Shading) -> Self {
+        self.shading = shading;
+        self
+    }
+
+}
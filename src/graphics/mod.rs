@@ -3,3 +3,13 @@ pub mod canvas;
 pub mod image;
 pub mod camera;
 pub mod color;
+pub mod blend;
+pub mod font;
+pub mod stroke;
+pub mod path;
+pub mod render_target;
+pub mod post_effect;
+pub mod material;
+pub mod obj;
+pub mod gltf;
+pub mod light;
@@ -0,0 +1,319 @@
+use crate::graphics::color::Color;
+use crate::graphics::image::Image;
+use crate::graphics::material::{Material3D, Shading};
+use crate::renderer::mesh::Mesh;
+use crate::renderer::vertex::Vertex3D;
+use cgmath::{InnerSpace, Vector3};
+use image::ImageReader;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Error returned when an OBJ or MTL file cannot be read or parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjError {
+    /// The file could not be read.
+    Io { path: String, message: String },
+    /// A line of the file is malformed, numbered from 1.
+    Parse { path: String, line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, message } => write!(f, "Failed to read {}: {}", path, message),
+            ObjError::Parse { path, line, message } => write!(f, "{}:{}: {}", path, line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// A part of an OBJ model, holding the faces of one group drawn with one material.
+#[derive(Clone, Debug)]
+pub struct ObjMesh {
+    /// Name of the object or group the faces belong to, empty if the file names none.
+    pub name: String,
+    pub mesh: Mesh<Vertex3D>,
+    pub material: Material3D,
+}
+
+impl Mesh<Vertex3D> {
+
+    /// Loads the meshes of a Wavefront OBJ file, one per group and material, with the materials of its MTL libraries.
+    ///
+    /// Faces with more than three corners are split into fans, so they should be convex.
+    /// Faces without normals get flat ones, and parts too large for 16-bit indices are split.
+    /// Missing libraries, materials and textures are reported and replaced by the default material.
+    pub fn from_obj(path: &str) -> Result<Vec<ObjMesh>, ObjError> {
+        let source = read(path)?;
+        let directory = Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut parser = ObjParser::new(path, directory);
+        for (number, line) in source.lines().enumerate() {
+            parser.line(line, number + 1)?;
+        }
+        Ok(parser.finish())
+    }
+
+}
+
+/// Key identifying a face corner by its position, texture coordinate and normal indices.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct ObjParser<'a> {
+    path: &'a str,
+    directory: PathBuf,
+    positions: Vec<Vector3<f32>>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<Vector3<f32>>,
+    materials: HashMap<String, Material3D>,
+    name: String,
+    material: Material3D,
+    vertices: Vec<Vertex3D>,
+    indices: Vec<u16>,
+    corners: HashMap<Corner, u16>,
+    meshes: Vec<ObjMesh>,
+}
+
+impl<'a> ObjParser<'a> {
+
+    fn new(path: &'a str, directory: PathBuf) -> Self {
+        Self {
+            path,
+            directory,
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            materials: HashMap::new(),
+            name: String::new(),
+            material: Material3D::default(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            corners: HashMap::new(),
+            meshes: Vec::new(),
+        }
+    }
+
+    fn line(&mut self, line: &str, number: usize) -> Result<(), ObjError> {
+        let error = |message: String| ObjError::Parse { path: self.path.to_string(), line: number, message };
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((keyword, rest)) = split_keyword(line) else { return Ok(()) };
+
+        match keyword {
+            "v" => self.positions.push(Vector3::from(parse_floats::<3>(rest, 3).map_err(error)?)),
+            "vn" => self.normals.push(Vector3::from(parse_floats::<3>(rest, 3).map_err(error)?).normalize()),
+            "vt" => {
+                // OBJ texture coordinates start at the bottom of the image
+                let [u, v] = parse_floats::<2>(rest, 1).map_err(error)?;
+                self.uvs.push([u, 1.0 - v]);
+            }
+            "f" => {
+                let corners = rest.split_whitespace()
+                    .map(|corner| self.corner(corner))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                if corners.len() < 3 {
+                    return Err(error(format!("Face has {} corners, at least 3 are needed", corners.len())));
+                }
+                self.face(&corners);
+            }
+            "o" | "g" => {
+                self.flush();
+                self.name = rest.to_string();
+            }
+            "usemtl" => {
+                self.flush();
+                self.material = self.materials.get(rest).cloned().unwrap_or_else(|| {
+                    warn!("{}:{}: Unknown material {}, using the default", self.path, number, rest);
+                    Material3D::default()
+                });
+            }
+            "mtllib" => {
+                for library in rest.split_whitespace() {
+                    let library = self.directory.join(library);
+                    match load_mtl(&library.to_string_lossy()) {
+                        Ok(materials) => self.materials.extend(materials),
+                        Err(ObjError::Io { path, message }) => warn!("Skipping material library {}: {}", path, message),
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+            _ => debug!("{}:{}: Ignoring unsupported statement {}", self.path, number, keyword),
+        }
+        Ok(())
+    }
+
+    /// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into indices from zero.
+    fn corner(&self, corner: &str) -> Result<Corner, String> {
+        let parts: Vec<_> = corner.split('/').collect();
+        if parts.len() > 3 {
+            return Err(format!("Face corner {} has too many indices", corner));
+        }
+        let optional = |part: Option<&&str>, count: usize, kind: &str| match part {
+            Some(&"") | None => Ok(None),
+            Some(part) => parse_index(part, count, kind).map(Some),
+        };
+        let position = parse_index(parts[0], self.positions.len(), "position")?;
+        let uv = optional(parts.get(1), self.uvs.len(), "texture coordinate")?;
+        let normal = optional(parts.get(2), self.normals.len(), "normal")?;
+        Ok((position, uv, normal))
+    }
+
+    fn face(&mut self, corners: &[Corner]) {
+        if self.vertices.len() + corners.len() > u16::MAX as usize + 1 {
+            self.flush();
+        }
+
+        // Newell's method gives a normal for faces that are not quite planar
+        let flat_normal = corners.iter().enumerate()
+            .fold(Vector3::new(0.0, 0.0, 0.0), |sum, (i, corner)| {
+                let a = self.positions[corner.0];
+                let b = self.positions[corners[(i + 1) % corners.len()].0];
+                sum + a.cross(b)
+            });
+        let flat_normal = if flat_normal.magnitude2() > 0.0 { flat_normal.normalize() } else { Vector3::unit_y() };
+
+        let indices: Vec<_> = corners.iter().map(|&corner| self.vertex(corner, flat_normal)).collect();
+        for i in 1..indices.len() - 1 {
+            self.indices.extend_from_slice(&[indices[0], indices[i], indices[i + 1]]);
+        }
+    }
+
+    fn vertex(&mut self, corner: Corner, flat_normal: Vector3<f32>) -> u16 {
+        let (position, uv, normal) = corner;
+        // Corners without a normal take the face's, so they are only shared within the face
+        if normal.is_some() && let Some(&index) = self.corners.get(&corner) {
+            return index;
+        }
+
+        self.vertices.push(Vertex3D {
+            position: self.positions[position].into(),
+            normal: normal.map_or(flat_normal, |n| self.normals[n]).into(),
+            uv: uv.map_or([0.0, 0.0], |t| self.uvs[t]),
+        });
+        let index = (self.vertices.len() - 1) as u16;
+        if normal.is_some() {
+            self.corners.insert(corner, index);
+        }
+        index
+    }
+
+    /// Moves the faces collected so far into a mesh of their own.
+    fn flush(&mut self) {
+        self.corners.clear();
+        if self.indices.is_empty() {
+            self.vertices.clear();
+            return;
+        }
+        self.meshes.push(ObjMesh {
+            name: self.name.clone(),
+            mesh: Mesh {
+                vertices: std::mem::take(&mut self.vertices),
+                indices: std::mem::take(&mut self.indices),
+            },
+            material: self.material.clone(),
+        });
+    }
+
+    fn finish(mut self) -> Vec<ObjMesh> {
+        self.flush();
+        self.meshes
+    }
+}
+
+/// Loads the materials of an MTL file by name, taking their diffuse color, opacity, diffuse texture and highlight.
+fn load_mtl(path: &str) -> Result<HashMap<String, Material3D>, ObjError> {
+    let source = read(path)?;
+    let directory = Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Material3D)> = None;
+
+    for (number, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError::Parse { path: path.to_string(), line: number + 1, message };
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((keyword, rest)) = split_keyword(line) else { continue };
+
+        if keyword == "newmtl" {
+            materials.extend(current.take());
+            current = Some((rest.to_string(), Material3D::default()));
+            continue;
+        }
+        let Some((_, material)) = current.as_mut() else {
+            return Err(error(format!("{} appears before any newmtl", keyword)));
+        };
+
+        match keyword {
+            "Kd" => {
+                let [r, g, b] = parse_floats::<3>(rest, 3).map_err(error)?;
+                material.color = Color::new(r, g, b, material.color.a);
+            }
+            "Ks" => {
+                // The shader takes a single highlight strength rather than a color
+                let strength = parse_floats::<3>(rest, 3).map_err(error)?.into_iter().fold(0.0, f32::max);
+                if let Shading::BlinnPhong { specular, .. } = &mut material.shading {
+                    *specular = strength;
+                }
+            }
+            "Ns" => {
+                let exponent = parse_floats::<1>(rest, 1).map_err(error)?[0];
+                if let Shading::BlinnPhong { shininess, .. } = &mut material.shading {
+                    *shininess = exponent;
+                }
+            }
+            "d" => material.color.a = parse_floats::<1>(rest, 1).map_err(error)?[0],
+            "Tr" => material.color.a = 1.0 - parse_floats::<1>(rest, 1).map_err(error)?[0],
+            "map_Kd" => {
+                // Options come before the file name, which is the last argument
+                let Some(file) = rest.split_whitespace().last() else {
+                    return Err(error("map_Kd needs a file name".to_string()));
+                };
+                let file = directory.join(file).to_string_lossy().to_string();
+                match ImageReader::open(&file).map_err(|e| e.to_string()).and_then(|reader| reader.decode().map_err(|e| e.to_string())) {
+                    Ok(image) => material.image = Some(Image { path: file, image }),
+                    Err(e) => warn!("Skipping texture {}: {}", file, e),
+                }
+            }
+            _ => debug!("{}:{}: Ignoring unsupported statement {}", path, number + 1, keyword),
+        }
+    }
+
+    materials.extend(current);
+    Ok(materials)
+}
+
+fn read(path: &str) -> Result<String, ObjError> {
+    std::fs::read_to_string(path).map_err(|e| ObjError::Io { path: path.to_string(), message: e.to_string() })
+}
+
+fn split_keyword(line: &str) -> Option<(&str, &str)> {
+    if line.is_empty() {
+        return None;
+    }
+    let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    Some((keyword, rest.trim()))
+}
+
+/// Parses up to `N` numbers, requiring at least `required` and leaving the rest at zero.
+fn parse_floats<const N: usize>(text: &str, required: usize) -> Result<[f32; N], String> {
+    let mut values = [0.0; N];
+    let mut count = 0;
+    for (i, part) in text.split_whitespace().enumerate().take(N) {
+        values[i] = part.parse().map_err(|_| format!("Invalid number {}", part))?;
+        count += 1;
+    }
+    if count < required {
+        return Err(format!("Expected {} numbers, found {}", required, count));
+    }
+    Ok(values)
+}
+
+/// Resolves a one-based index, or a negative one counting back from the end, into an index from zero.
+fn parse_index(text: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = text.parse().map_err(|_| format!("Invalid {} index {}", kind, text))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("The {} index {} is out of range, there are {}", kind, index, count));
+    }
+    Ok(resolved as usize)
+}
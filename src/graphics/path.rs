@@ -0,0 +1,215 @@
+use crate::graphics::stroke::Stroke;
+use crate::renderer::mesh::Mesh;
+use crate::renderer::tessellation::{self, TriangleBuilder};
+use crate::renderer::vertex::Vertex2D;
+use cgmath::{EuclideanSpace, InnerSpace, Point2, Vector2};
+use std::f32::consts::PI;
+
+/// Decides which areas enclosed by a path are inside when it is filled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside where the contours wind around a point a non-zero number of times.
+    #[default]
+    NonZero,
+    /// Inside where a ray from the point crosses the contours an odd number of times.
+    EvenOdd,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PathCommand {
+    MoveTo(Point2<f32>),
+    LineTo(Point2<f32>),
+    QuadTo(Point2<f32>, Point2<f32>),
+    CubicTo(Point2<f32>, Point2<f32>, Point2<f32>),
+    ArcTo(Point2<f32>, Point2<f32>, f32),
+    Close,
+}
+
+/// A vector shape made of lines and curves that can be filled or stroked into a mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    commands: Vec<PathCommand>,
+    tolerance: f32,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Path {
+
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            tolerance: 0.01,
+        }
+    }
+
+    /// Sets the maximum distance between curves and the line segments approximating them.
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance.max(f32::EPSILON);
+        self
+    }
+
+    /// Starts a new contour at the point.
+    pub fn move_to(mut self, point: Point2<f32>) -> Self {
+        self.commands.push(PathCommand::MoveTo(point));
+        self
+    }
+
+    /// Adds a straight line to the point.
+    pub fn line_to(mut self, point: Point2<f32>) -> Self {
+        self.commands.push(PathCommand::LineTo(point));
+        self
+    }
+
+    /// Adds a quadratic Bezier curve to the point.
+    pub fn quad_to(mut self, control: Point2<f32>, point: Point2<f32>) -> Self {
+        self.commands.push(PathCommand::QuadTo(control, point));
+        self
+    }
+
+    /// Adds a cubic Bezier curve to the point.
+    pub fn cubic_to(mut self, control1: Point2<f32>, control2: Point2<f32>, point: Point2<f32>) -> Self {
+        self.commands.push(PathCommand::CubicTo(control1, control2, point));
+        self
+    }
+
+    /// Adds a circular arc of the given radius tangent to the lines from the current point to `corner` and from `corner` to `point`,
+    /// connected to the current point with a straight line.
+    pub fn arc_to(mut self, corner: Point2<f32>, point: Point2<f32>, radius: f32) -> Self {
+        self.commands.push(PathCommand::ArcTo(corner, point, radius));
+        self
+    }
+
+    /// Closes the current contour with a line back to its start.
+    pub fn close(mut self) -> Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    /// Tessellates the area enclosed by the path, treating every contour as closed.
+    pub fn fill(&self, rule: FillRule, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let contours: Vec<_> = self.flatten().into_iter().map(|(points, _)| points).collect();
+        let mut builder = TriangleBuilder::new();
+        tessellation::fill_contours(&mut builder, &contours, rule);
+        builder.build(color)
+    }
+
+    /// Tessellates the outline of the path.
+    pub fn stroke(&self, stroke: &Stroke, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let mut builder = TriangleBuilder::new();
+        for (points, closed) in self.flatten() {
+            tessellation::stroke_polyline(&mut builder, &points, closed, stroke);
+        }
+        builder.build(color)
+    }
+
+    /// Approximates the path with polylines, returning each contour and whether it was closed.
+    fn flatten(&self) -> Vec<(Vec<Vector2<f32>>, bool)> {
+        let mut contours = Vec::new();
+        let mut points: Vec<Vector2<f32>> = Vec::new();
+        let mut start = Vector2::new(0.0, 0.0);
+
+        for command in &self.commands {
+            // Drawing after a close continues from the start of the closed contour
+            let current = points.last().copied().unwrap_or(start);
+
+            match *command {
+                PathCommand::MoveTo(p) => {
+                    if points.len() > 1 {
+                        contours.push((std::mem::take(&mut points), false));
+                    }
+                    points.clear();
+                    start = p.to_vec();
+                    points.push(start);
+                }
+                PathCommand::LineTo(p) => {
+                    Self::begin(&mut points, current);
+                    points.push(p.to_vec());
+                }
+                PathCommand::QuadTo(c, p) => {
+                    Self::begin(&mut points, current);
+                    let (c, p) = (c.to_vec(), p.to_vec());
+                    let deviation = (current - c * 2.0 + p).magnitude();
+                    for t in Self::steps((deviation / (4.0 * self.tolerance)).sqrt()) {
+                        let u = 1.0 - t;
+                        points.push(current * (u * u) + c * (2.0 * u * t) + p * (t * t));
+                    }
+                }
+                PathCommand::CubicTo(c1, c2, p) => {
+                    Self::begin(&mut points, current);
+                    let (c1, c2, p) = (c1.to_vec(), c2.to_vec(), p.to_vec());
+                    let deviation = (current - c1 * 2.0 + c2).magnitude().max((c1 - c2 * 2.0 + p).magnitude());
+                    for t in Self::steps((3.0 * deviation / (4.0 * self.tolerance)).sqrt()) {
+                        let u = 1.0 - t;
+                        points.push(current * (u * u * u) + c1 * (3.0 * u * u * t) + c2 * (3.0 * u * t * t) + p * (t * t * t));
+                    }
+                }
+                PathCommand::ArcTo(corner, p, radius) => {
+                    Self::begin(&mut points, current);
+                    self.flatten_arc(&mut points, current, corner.to_vec(), p.to_vec(), radius);
+                }
+                PathCommand::Close => {
+                    if points.len() > 1 {
+                        contours.push((std::mem::take(&mut points), true));
+                    }
+                    points.clear();
+                }
+            }
+        }
+
+        if points.len() > 1 {
+            contours.push((points, false));
+        }
+        contours
+    }
+
+    /// Makes sure the contour starts at the current point.
+    fn begin(points: &mut Vec<Vector2<f32>>, current: Vector2<f32>) {
+        if points.is_empty() {
+            points.push(current);
+        }
+    }
+
+    /// Returns the parameters of the points dividing a curve into the given number of segments, excluding the start.
+    fn steps(segments: f32) -> impl Iterator<Item = f32> {
+        let segments = segments.ceil().clamp(1.0, 256.0) as usize;
+        (1..=segments).map(move |i| i as f32 / segments as f32)
+    }
+
+    fn flatten_arc(&self, points: &mut Vec<Vector2<f32>>, from: Vector2<f32>, corner: Vector2<f32>, to: Vector2<f32>, radius: f32) {
+        let (a, b) = (from - corner, to - corner);
+        let angle = a.angle(b).0.abs();
+        if radius <= 0.0 || a.magnitude2() <= f32::EPSILON || b.magnitude2() <= f32::EPSILON || !(f32::EPSILON..PI - 1e-4).contains(&angle) {
+            points.push(corner);
+            return;
+        }
+
+        // The arc touches both lines at the same distance from the corner
+        let (a, b) = (a.normalize(), b.normalize());
+        let tangent = radius / (angle / 2.0).tan();
+        let center = corner + (a + b).normalize() * (radius / (angle / 2.0).sin());
+        let start = corner + a * tangent;
+        let end = corner + b * tangent;
+        points.push(start);
+
+        let start_angle = (start.y - center.y).atan2(start.x - center.x);
+        let mut sweep = (end.y - center.y).atan2(end.x - center.x) - start_angle;
+        if sweep > PI {
+            sweep -= 2.0 * PI;
+        } else if sweep < -PI {
+            sweep += 2.0 * PI;
+        }
+
+        // Keep each chord within the tolerance of the arc
+        let step = 2.0 * (1.0 - (self.tolerance / radius).min(1.0)).acos();
+        for t in Self::steps(sweep.abs() / step) {
+            let angle = start_angle + sweep * t;
+            points.push(center + Vector2::new(angle.cos(), angle.sin()) * radius);
+        }
+    }
+
+}
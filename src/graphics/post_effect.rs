@@ -0,0 +1,104 @@
+use crate::graphics::image::Image;
+use crate::graphics::material::{read_shader, ShaderError};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// A full-screen effect applied to the finished frame, defined by a WGSL fragment shader and its parameters.
+///
+/// The shader must define `fs_main` and can use everything declared in the shared prelude: `VertexOutput` with the
+/// frame's `uv`, the frame as `source_texture` and `source_sampler`, the effect's image as `extra_texture` and
+/// `extra_sampler`, and `post.resolution` and `post.params` holding the frame size in pixels and the effect's parameters.
+#[derive(Clone, Debug)]
+pub struct PostEffect {
+    pub shader: String,
+    /// File the shader was loaded from, watched for changes when shader hot reload is enabled.
+    pub shader_path: Option<String>,
+    pub params: [f32; 4],
+    /// Extra image sampled by the shader, such as a color lookup table.
+    pub image: Option<Image>,
+}
+
+impl PostEffect {
+
+    /// Creates an effect from the WGSL source of its fragment shader.
+    pub fn new(shader: &str) -> Self {
+        Self {
+            shader: shader.to_string(),
+            shader_path: None,
+            params: [0.0; 4],
+            image: None,
+        }
+    }
+
+    /// Loads an effect from a WGSL file.
+    pub fn from_file(path: &str) -> Result<Self, ShaderError> {
+        let shader = read_shader(path)?;
+        Ok(Self {
+            shader_path: Some(path.to_string()),
+            ..Self::new(&shader)
+        })
+    }
+
+    pub fn with_params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_image(mut self, image: Image) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Blurs the frame over the radius in pixels.
+    pub fn blur(radius: f32) -> Self {
+        Self::new(include_str!("../shaders/post/blur.wgsl")).with_params([radius, 0.0, 0.0, 0.0])
+    }
+
+    /// Makes colors brighter than the threshold glow over the radius in pixels.
+    pub fn bloom(threshold: f32, intensity: f32, radius: f32) -> Self {
+        Self::new(include_str!("../shaders/post/bloom.wgsl")).with_params([threshold, intensity, radius, 0.0])
+    }
+
+    /// Grades colors with a lookup table laid out like [`PostEffect::identity_lut`].
+    pub fn lut(table: Image) -> Self {
+        Self::new(include_str!("../shaders/post/lut.wgsl")).with_params([1.0, 0.0, 0.0, 0.0]).with_image(table)
+    }
+
+    /// Darkens the frame towards its corners, starting at the radius where 1 is the middle of the edges.
+    pub fn vignette(strength: f32, radius: f32) -> Self {
+        Self::new(include_str!("../shaders/post/vignette.wgsl")).with_params([strength, radius, 0.5, 0.0])
+    }
+
+    /// Imitates an old monitor with dark scanlines and a curved screen.
+    pub fn crt(scanline_intensity: f32, curvature: f32) -> Self {
+        Self::new(include_str!("../shaders/post/crt.wgsl")).with_params([scanline_intensity, curvature, 0.0, 0.0])
+    }
+
+    /// Removes the given amount of color, from 0 to 1.
+    pub fn grayscale(amount: f32) -> Self {
+        Self::new(include_str!("../shaders/post/grayscale.wgsl")).with_params([amount, 0.0, 0.0, 0.0])
+    }
+
+    /// Creates a lookup table that leaves colors unchanged, with `size` steps per channel.
+    ///
+    /// The table has `size` slices of `size` by `size` pixels side by side. Blue increases per slice, red to the right
+    /// and green downwards within a slice. Saving it and editing it in an image editor is a simple way to make a grade.
+    pub fn identity_lut(size: u32) -> Image {
+        let size = size.max(2);
+        let step = 255.0 / (size - 1) as f32;
+        let table = RgbaImage::from_fn(size * size, size, |x, y| {
+            let channel = |value: u32| (value as f32 * step).round() as u8;
+            Rgba([channel(x % size), channel(y), channel(x / size), 255])
+        });
+
+        Image {
+            path: format!("__identity_lut_{}", size),
+            image: DynamicImage::ImageRgba8(table),
+        }
+    }
+
+    /// Returns the keys the shader is compiled and cached under, its file or an empty string if it has none, then its source.
+    pub(crate) fn shader_key(&self) -> (&str, &str) {
+        (self.shader_path.as_deref().unwrap_or(""), &self.shader)
+    }
+
+}
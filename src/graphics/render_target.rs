@@ -0,0 +1,65 @@
+use crate::graphics::canvas::Canvas;
+use crate::graphics::image::Image;
+use image::DynamicImage;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+static RENDER_TARGET_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// An offscreen texture that a canvas can be drawn into and that can then be drawn like an image.
+///
+/// The texture lives on the GPU and keeps its contents between frames, so it only needs to be redrawn when it changes.
+/// Clones share the texture, which is released on the first frame after every clone has been dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderTarget {
+    id: Arc<u64>,
+    width: u32,
+    height: u32,
+}
+
+impl RenderTarget {
+
+    /// Creates a render target of the given size in pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            id: Arc::new(RENDER_TARGET_COUNTER.fetch_add(1, Ordering::Relaxed)),
+            width: width.max(1),
+            height: height.max(1),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns an empty canvas sized to match the render target.
+    pub fn canvas(&self) -> Canvas {
+        Canvas::new(self.width as f32, self.height as f32)
+    }
+
+    /// Returns an image referring to the render target's texture, for use with [`DrawStyle::with_image`](crate::prelude::DrawStyle::with_image).
+    ///
+    /// The pixels only exist on the GPU, so the returned image holds no pixel data of its own
+    /// and draws untextured once the render target has been released.
+    pub fn image(&self) -> Image {
+        Image {
+            path: self.key(),
+            image: DynamicImage::new_rgba8(0, 0),
+        }
+    }
+
+    /// Returns the key of the render target's texture in the texture cache.
+    pub(crate) fn key(&self) -> String {
+        format!("__render_target_{}", self.id)
+    }
+
+    /// Returns a handle that stops upgrading once every clone of the render target has been dropped.
+    pub(crate) fn handle(&self) -> Weak<u64> {
+        Arc::downgrade(&self.id)
+    }
+
+}
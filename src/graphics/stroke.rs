@@ -0,0 +1,64 @@
+/// How two segments of a stroked line are connected.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LineJoin {
+    /// Extends the outer edges until they meet, falling back to a bevel past the miter limit.
+    #[default]
+    Miter,
+    /// Rounds the corner with an arc.
+    Round,
+    /// Cuts the corner off with a straight edge.
+    Bevel,
+}
+
+/// How the ends of an open stroked line are drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LineCap {
+    /// Ends exactly at the end points.
+    #[default]
+    Butt,
+    /// Extends past the end points by half the line width.
+    Square,
+    /// Ends with a half circle around the end points.
+    Round,
+}
+
+/// Describes how lines and outlines are stroked.
+///
+/// There is no default width, as world units cover a different number of pixels under every camera.
+/// Lines drawn with a style that has no stroke are one pixel wide on screen instead.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stroke {
+    /// Width in the local units of the drawn shape.
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Maximum ratio of miter length to half the line width before a miter join is beveled.
+    pub miter_limit: f32,
+}
+
+impl Stroke {
+
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            miter_limit: 4.0,
+        }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+}
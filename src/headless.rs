@@ -0,0 +1,76 @@
+use crate::graphics::canvas::Canvas;
+use crate::graphics::image::Image;
+use crate::renderer::state::{RenderState, RenderStats};
+use std::sync::atomic::{AtomicU64, Ordering};
+use winit::dpi::PhysicalSize;
+
+static FRAME_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Renders a [`Canvas`] into an offscreen texture instead of a window.
+/// Useful for tests, CI and servers where no display is available.
+#[derive(Debug)]
+pub struct HeadlessRenderer {
+    state: RenderState,
+    width: u32,
+    height: u32,
+}
+
+impl HeadlessRenderer {
+
+    /// Creates a headless renderer with a render target of the given size in pixels.
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        Ok(Self {
+            state: RenderState::new_headless(width, height)?,
+            width,
+            height,
+        })
+    }
+
+    /// Returns information about the adapter used for rendering.
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.state.adapter_info()
+    }
+
+    /// Returns statistics about the last rendered frame.
+    pub fn stats(&self) -> RenderStats {
+        self.state.stats()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns a canvas sized to match the render target.
+    pub fn canvas(&self) -> Canvas {
+        Canvas::new(self.width as f32, self.height as f32)
+    }
+
+    /// Enables or disables reloading material and post effect shaders loaded from files when the files change.
+    pub fn set_shader_hot_reload(&mut self, enabled: bool) {
+        self.state.set_shader_hot_reload(enabled);
+    }
+
+    /// Resizes the render target.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.state.resize(PhysicalSize::new(width, height));
+    }
+
+    /// Renders the canvas and returns the resulting pixels.
+    pub fn render(&mut self, canvas: &Canvas) -> Result<Image, String> {
+        self.state.render(canvas);
+        let pixels = self.state.read_frame()?;
+        let frame = FRAME_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        Ok(Image {
+            path: format!("headless_frame_{}", frame),
+            image: image::DynamicImage::ImageRgba8(pixels)
+        })
+    }
+
+}
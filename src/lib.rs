@@ -1,14 +1,31 @@
 mod graphics;
 mod renderer;
 pub mod app;
+pub mod headless;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub mod prelude {
     pub use crate::app::*;
+    pub use crate::headless::*;
+    pub use crate::graphics::blend::*;
     pub use crate::graphics::camera::*;
     pub use crate::graphics::canvas::*;
     pub use crate::graphics::color::*;
+    pub use crate::graphics::font::{Font, TextAlign};
+    pub use crate::graphics::path::*;
+    pub use crate::graphics::post_effect::*;
+    pub use crate::graphics::stroke::*;
     pub use crate::graphics::image::*;
+    pub use crate::graphics::light::*;
+    pub use crate::graphics::material::*;
+    pub use crate::graphics::gltf::*;
+    pub use crate::graphics::obj::*;
+    pub use crate::graphics::render_target::*;
     pub use crate::graphics::transform::*;
+    pub use crate::renderer::mesh::*;
+    pub use crate::renderer::state::RenderStats;
+    pub use crate::renderer::vertex::{InstanceData, Vertex2D, Vertex3D};
     pub use winit::keyboard::KeyCode;
     pub use cgmath::{Point2, Point3, Vector2, Vector3};
 }
\ No newline at end of file
@@ -0,0 +1,89 @@
+use bytemuck::Pod;
+use log::debug;
+use std::ops::Range;
+use wgpu::{Buffer, BufferUsages, Device, Queue};
+
+/// A GPU buffer that is reused across frames and sub-allocated for each frame's geometry.
+///
+/// Data is staged on the CPU with [`BufferArena::push`] and uploaded in a single write by [`BufferArena::upload`].
+/// The buffer grows to the next power of two whenever a frame needs more room than it has.
+#[derive(Debug)]
+pub struct BufferArena {
+    label: &'static str,
+    usage: BufferUsages,
+    buffer: Buffer,
+    staging: Vec<u8>,
+    reallocations: usize,
+}
+
+impl BufferArena {
+
+    pub fn new(device: &Device, label: &'static str, usage: BufferUsages, capacity: u64) -> Self {
+        Self {
+            label,
+            usage: usage | BufferUsages::COPY_DST,
+            buffer: Self::create_buffer(device, label, usage | BufferUsages::COPY_DST, capacity),
+            staging: Vec::new(),
+            reallocations: 0,
+        }
+    }
+
+    /// Discards the data staged for the previous frame.
+    pub fn clear(&mut self) {
+        self.staging.clear();
+    }
+
+    /// Stages the items and returns the byte range they will occupy in the buffer.
+    pub fn push<T: Pod>(&mut self, items: &[T]) -> Range<u64> {
+        let start = self.staging.len();
+        self.staging.extend_from_slice(bytemuck::cast_slice(items));
+        let end = self.staging.len();
+
+        // Keep every allocation aligned for vertex and index buffer offsets
+        let aligned = end.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize);
+        self.staging.resize(aligned, 0);
+
+        start as u64..end as u64
+    }
+
+    /// Uploads the staged data, growing the buffer if needed, and returns the number of bytes written.
+    pub fn upload(&mut self, device: &Device, queue: &Queue) -> u64 {
+        let size = self.staging.len() as u64;
+        if size == 0 {
+            return 0;
+        }
+
+        if size > self.buffer.size() {
+            let capacity = size.next_power_of_two();
+            debug!("Growing {} from {} to {} bytes", self.label, self.buffer.size(), capacity);
+            self.buffer = Self::create_buffer(device, self.label, self.usage, capacity);
+            self.reallocations += 1;
+        }
+
+        queue.write_buffer(&self.buffer, 0, &self.staging);
+        size
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.buffer.size()
+    }
+
+    /// Returns how many times the buffer had to grow since creation.
+    pub fn reallocations(&self) -> usize {
+        self.reallocations
+    }
+
+    fn create_buffer(device: &Device, label: &'static str, usage: BufferUsages, capacity: u64) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: capacity.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+}
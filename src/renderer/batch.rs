@@ -0,0 +1,344 @@
+use crate::graphics::blend::BlendMode;
+use crate::graphics::canvas::{ClipRect, MaskMode};
+use crate::graphics::light::Lighting;
+use crate::graphics::material::{Material, Material3D};
+use crate::renderer::glyph_atlas::GlyphAtlas;
+use crate::renderer::mesh::MeshBuilder2D;
+use crate::renderer::state::DrawCommand;
+use crate::renderer::vertex::{InstanceData, Vertex2D, Vertex3D};
+use cgmath::{Matrix4, SquareMatrix, Vector4};
+
+/// Maps the full-screen quad's vertices straight to clip space, halfway into the depth range.
+const FULLSCREEN_MATRIX: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 0.0, 0.0],
+    [0.0, 0.0, 0.5, 1.0],
+];
+
+/// How a batch uses the stencil buffer, which holds how many nested masks cover each pixel.
+///
+/// Every mode only touches pixels whose stencil value equals the batch's stencil reference.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StencilMode {
+    /// Draws color where the pixel is inside all current masks.
+    #[default]
+    Test,
+    /// Adds the covered pixels to the next mask level without drawing color.
+    Increment,
+    /// Removes the covered pixels from the current mask level without drawing color.
+    Decrement,
+}
+
+/// Texture sampled by a batch.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BatchTexture<P = String> {
+    /// An image in the texture cache, by path.
+    Image(P),
+    /// The glyph atlas, kept apart from the texture cache so no image path can refer to it.
+    GlyphAtlas,
+}
+
+impl<P> BatchTexture<P> {
+    fn as_ref(&self) -> BatchTexture<&P> {
+        match self {
+            BatchTexture::Image(path) => BatchTexture::Image(path),
+            BatchTexture::GlyphAtlas => BatchTexture::GlyphAtlas,
+        }
+    }
+}
+
+impl<P: Clone> BatchTexture<&P> {
+    fn cloned(&self) -> BatchTexture<P> {
+        match self {
+            BatchTexture::Image(path) => BatchTexture::Image((*path).clone()),
+            BatchTexture::GlyphAtlas => BatchTexture::GlyphAtlas,
+        }
+    }
+}
+
+/// Geometry from one or more consecutive draw commands that can be issued with a single draw call.
+#[derive(Clone, Debug)]
+pub struct DrawBatch {
+    pub camera_matrix: [[f32; 4]; 4],
+    pub transform: Matrix4<f32>,
+    pub texture: Option<BatchTexture>,
+    pub blend_mode: BlendMode,
+    /// Custom shader the batch is drawn with instead of the built-in one.
+    pub material: Option<Material>,
+    /// Screen rectangle the batch is clipped to.
+    pub clip: Option<ClipRect>,
+    pub stencil: StencilMode,
+    pub stencil_reference: u32,
+    pub vertices: Vec<Vertex2D>,
+    /// Vertices of a 3D mesh, drawn with the 3D pipeline instead of the 2D vertices.
+    pub vertices_3d: Vec<Vertex3D>,
+    pub indices: Vec<u16>,
+    /// Per-instance data when the batch is drawn with the instanced pipeline.
+    pub instances: Vec<InstanceData>,
+    /// Surface of a 3D mesh, only set for batches drawn with the 3D pipeline.
+    pub material_3d: Option<Material3D>,
+    /// Lighting of a 3D mesh, unlit when not set.
+    pub lighting: Option<Lighting>,
+    instanced: bool,
+    merged: bool,
+}
+
+impl DrawBatch {
+
+    pub fn is_instanced(&self) -> bool {
+        self.instanced
+    }
+
+    pub fn is_3d(&self) -> bool {
+        self.material_3d.is_some()
+    }
+
+    /// Returns the number of instances to draw.
+    pub fn instance_count(&self) -> u32 {
+        if self.instanced { self.instances.len() as u32 } else { 1 }
+    }
+
+    fn new(key: &BatchKey, transform: Matrix4<f32>, merged: bool) -> Self {
+        Self {
+            camera_matrix: *key.camera_matrix,
+            transform,
+            texture: key.texture.as_ref().map(BatchTexture::cloned),
+            blend_mode: key.blend_mode,
+            material: key.material.cloned(),
+            clip: key.clip,
+            stencil: key.stencil,
+            stencil_reference: key.stencil_reference,
+            vertices: Vec::new(),
+            vertices_3d: Vec::new(),
+            indices: Vec::new(),
+            instances: Vec::new(),
+            material_3d: None,
+            lighting: None,
+            instanced: false,
+            merged,
+        }
+    }
+
+    /// Returns true if the geometry can be appended to this batch.
+    fn accepts(&self, key: &BatchKey, vertex_count: usize) -> bool {
+        self.merged
+            && self.camera_matrix == *key.camera_matrix
+            && self.texture.as_ref().map(BatchTexture::as_ref) == key.texture
+            && self.blend_mode == key.blend_mode
+            && self.material.as_ref() == key.material
+            && self.clip == key.clip
+            && self.stencil == key.stencil
+            && self.stencil_reference == key.stencil_reference
+            && self.vertices.len() + vertex_count <= u16::MAX as usize + 1
+    }
+
+}
+
+/// The state a draw needs to share with a batch to be merged into it.
+struct BatchKey<'a> {
+    camera_matrix: &'a [[f32; 4]; 4],
+    texture: Option<BatchTexture<&'a String>>,
+    blend_mode: BlendMode,
+    material: Option<&'a Material>,
+    clip: Option<ClipRect>,
+    stencil: StencilMode,
+    stencil_reference: u32,
+}
+
+impl<'a> BatchKey<'a> {
+
+    /// Returns the key of a draw that writes to the stencil buffer, unaffected by clip rectangles.
+    fn mask(camera_matrix: &'a [[f32; 4]; 4], stencil: StencilMode, stencil_reference: u32) -> Self {
+        Self {
+            camera_matrix,
+            texture: None,
+            blend_mode: BlendMode::default(),
+            material: None,
+            clip: None,
+            stencil,
+            stencil_reference,
+        }
+    }
+
+}
+
+/// Merges consecutive 2D draw commands sharing a camera, texture, blend mode, material, clip rectangle and mask level into batches.
+///
+/// Merged geometry is transformed on the CPU so the batch can be drawn with an identity transform.
+/// Commands whose transform moves vertices off the z = 1 plane used by the 2D shader cannot be flattened
+/// this way and keep their own batch and transform. Text is turned into textured quads using the glyph atlas.
+///
+/// Masks keep a count of nested masks per pixel in the stencil buffer. An inside mask increments the pixels its mesh covers.
+/// An outside mask increments every pixel and then decrements those its mesh covers. Popping a mask decrements every pixel
+/// at its level, and regular draws only touch pixels at the current level.
+pub fn build_batches(commands: &[DrawCommand], glyph_atlas: &GlyphAtlas) -> Vec<DrawBatch> {
+    let mut batches: Vec<DrawBatch> = Vec::new();
+    let mut clip = None;
+    let mut mask_level = 0;
+
+    for command in commands {
+        match command {
+            DrawCommand::DrawMesh2D { mesh, camera_matrix, transform, style } => {
+                let key = BatchKey {
+                    camera_matrix,
+                    texture: style.image.as_ref().map(|img| BatchTexture::Image(&img.path)),
+                    blend_mode: style.blend_mode,
+                    material: style.material.as_ref(),
+                    clip,
+                    stencil: StencilMode::Test,
+                    stencil_reference: mask_level,
+                };
+                push_mesh(&mut batches, &key, *transform, &mesh.vertices, &mesh.indices);
+            }
+            DrawCommand::DrawInstanced2D { mesh, camera_matrix, instances, style } => {
+                let key = BatchKey {
+                    camera_matrix,
+                    texture: style.image.as_ref().map(|img| BatchTexture::Image(&img.path)),
+                    blend_mode: style.blend_mode,
+                    material: None,
+                    clip,
+                    stencil: StencilMode::Test,
+                    stencil_reference: mask_level,
+                };
+                batches.push(DrawBatch {
+                    vertices: mesh.vertices.clone(),
+                    indices: mesh.indices.clone(),
+                    instances: instances.clone(),
+                    instanced: true,
+                    ..DrawBatch::new(&key, Matrix4::identity(), false)
+                });
+            }
+            DrawCommand::DrawText { glyphs, camera_matrix, transform, style, .. } => {
+                let color: [f32; 4] = style.color.into();
+                let matrix = *transform;
+                let key = BatchKey {
+                    camera_matrix,
+                    texture: Some(BatchTexture::GlyphAtlas),
+                    blend_mode: style.blend_mode,
+                    material: None,
+                    clip,
+                    stencil: StencilMode::Test,
+                    stencil_reference: mask_level,
+                };
+                let mut vertices = Vec::with_capacity(glyphs.len() * 4);
+                let mut indices = Vec::with_capacity(glyphs.len() * 6);
+
+                for glyph in glyphs {
+                    let Some([u0, v0, u1, v1]) = glyph_atlas.uv_rect(&glyph.key) else {
+                        continue;
+                    };
+
+                    // Split very long texts so they stay within 16-bit indices
+                    if vertices.len() + 4 > u16::MAX as usize + 1 {
+                        push_mesh(&mut batches, &key, matrix, &vertices, &indices);
+                        vertices.clear();
+                        indices.clear();
+                    }
+
+                    let [x0, y0, x1, y1] = glyph.rect;
+                    let base = vertices.len() as u16;
+                    vertices.extend_from_slice(&[
+                        Vertex2D { position: [x0, y1], uv: [u0, v0], color },
+                        Vertex2D { position: [x1, y1], uv: [u1, v0], color },
+                        Vertex2D { position: [x0, y0], uv: [u0, v1], color },
+                        Vertex2D { position: [x1, y0], uv: [u1, v1], color },
+                    ]);
+                    indices.extend_from_slice(&[base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+                }
+
+                push_mesh(&mut batches, &key, matrix, &vertices, &indices);
+            }
+            DrawCommand::DrawMesh3D { mesh, camera_matrix, transform, material, lighting } => {
+                let key = BatchKey {
+                    camera_matrix,
+                    texture: material.image.as_ref().map(|img| BatchTexture::Image(&img.path)),
+                    blend_mode: BlendMode::default(),
+                    material: None,
+                    clip,
+                    stencil: StencilMode::Test,
+                    stencil_reference: mask_level,
+                };
+                batches.push(DrawBatch {
+                    vertices_3d: mesh.vertices.clone(),
+                    indices: mesh.indices.clone(),
+                    material_3d: Some(material.clone()),
+                    lighting: lighting.clone(),
+                    ..DrawBatch::new(&key, *transform, false)
+                });
+            }
+            DrawCommand::SetClipRect(rect) => {
+                clip = *rect;
+            }
+            DrawCommand::PushMask { mesh, camera_matrix, transform, mode } => {
+                match mode {
+                    MaskMode::Inside => {
+                        let key = BatchKey::mask(camera_matrix, StencilMode::Increment, mask_level);
+                        push_mesh(&mut batches, &key, *transform, &mesh.vertices, &mesh.indices);
+                    }
+                    MaskMode::Outside => {
+                        push_fullscreen(&mut batches, StencilMode::Increment, mask_level);
+                        let key = BatchKey::mask(camera_matrix, StencilMode::Decrement, mask_level + 1);
+                        push_mesh(&mut batches, &key, *transform, &mesh.vertices, &mesh.indices);
+                    }
+                }
+                mask_level += 1;
+            }
+            DrawCommand::PopMask => {
+                if mask_level > 0 {
+                    push_fullscreen(&mut batches, StencilMode::Decrement, mask_level);
+                    mask_level -= 1;
+                }
+            }
+            // Render targets are drawn in passes of their own
+            DrawCommand::RenderToTarget { .. } => {}
+        }
+    }
+
+    batches
+}
+
+/// Appends the mesh to the last batch if possible, otherwise starts a new one.
+fn push_mesh(batches: &mut Vec<DrawBatch>, key: &BatchKey, matrix: Matrix4<f32>, vertices: &[Vertex2D], indices: &[u16]) {
+    if !is_planar(&matrix) {
+        batches.push(DrawBatch {
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+            ..DrawBatch::new(key, matrix, false)
+        });
+        return;
+    }
+
+    let batch = match batches.last_mut() {
+        Some(batch) if batch.accepts(key, vertices.len()) => batch,
+        _ => {
+            batches.push(DrawBatch::new(key, Matrix4::identity(), true));
+            batches.last_mut().unwrap()
+        }
+    };
+
+    let base = batch.vertices.len() as u16;
+    batch.vertices.extend(vertices.iter().map(|v| {
+        let p = matrix * Vector4::new(v.position[0], v.position[1], 1.0, 1.0);
+        Vertex2D { position: [p.x, p.y], ..*v }
+    }));
+    batch.indices.extend(indices.iter().map(|i| base + i));
+}
+
+/// Adds a quad covering the whole target that only writes to the stencil buffer.
+fn push_fullscreen(batches: &mut Vec<DrawBatch>, stencil: StencilMode, stencil_reference: u32) {
+    let quad = MeshBuilder2D::from_rectangle(2.0, 2.0, [1.0; 4]);
+    let key = BatchKey::mask(&FULLSCREEN_MATRIX, stencil, stencil_reference);
+    push_mesh(batches, &key, Matrix4::identity(), &quad.vertices, &quad.indices);
+}
+
+/// Returns true if the matrix keeps points on the z = 1 plane where the 2D shader places them.
+fn is_planar(matrix: &Matrix4<f32>) -> bool {
+    const EPSILON: f32 = 1e-6;
+    matrix.x.z.abs() < EPSILON
+        && matrix.y.z.abs() < EPSILON
+        && (matrix.z.z + matrix.w.z - 1.0).abs() < EPSILON
+        && matrix.x.w.abs() < EPSILON
+        && matrix.y.w.abs() < EPSILON
+        && (matrix.z.w + matrix.w.w - 1.0).abs() < EPSILON
+}
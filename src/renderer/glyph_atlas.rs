@@ -0,0 +1,166 @@
+use crate::graphics::font::{Font, GlyphKey};
+use image::{Rgba, RgbaImage};
+use log::warn;
+use std::collections::HashMap;
+
+const INITIAL_SIZE: u32 = 512;
+const MAX_SIZE: u32 = 4096;
+const PADDING: u32 = 1;
+
+/// Packs rasterized glyphs into a single RGBA image, white with the glyph coverage in the alpha channel.
+///
+/// Glyphs are placed on shelves left to right. The atlas doubles in size when it runs out of room
+/// and is cleared once it reaches its maximum size, at most once per frame so the glyphs of a frame can be prepared again after it.
+#[derive(Debug)]
+pub struct GlyphAtlas {
+    image: RgbaImage,
+    entries: HashMap<GlyphKey, [u32; 4]>,
+    cursor_x: u32,
+    cursor_y: u32,
+    row_height: u32,
+    /// Region changed since the changes were last taken, as x, y, width and height.
+    dirty: Option<[u32; 4]>,
+    /// Whether the atlas was cleared since the frame began.
+    cleared: bool,
+}
+
+impl GlyphAtlas {
+
+    pub fn new() -> Self {
+        Self {
+            image: RgbaImage::from_pixel(INITIAL_SIZE, INITIAL_SIZE, Rgba([255, 255, 255, 0])),
+            entries: HashMap::new(),
+            cursor_x: PADDING,
+            cursor_y: PADDING,
+            row_height: 0,
+            dirty: Some([0, 0, INITIAL_SIZE, INITIAL_SIZE]),
+            cleared: false,
+        }
+    }
+
+    /// Starts preparing the glyphs of a new frame.
+    pub fn begin_frame(&mut self) {
+        self.cleared = false;
+    }
+
+    /// Returns true if the atlas was cleared to make room since the frame began, dropping the glyphs prepared before.
+    pub fn was_cleared(&self) -> bool {
+        self.cleared
+    }
+
+    /// Rasterizes the glyph into the atlas unless it is already there.
+    pub fn prepare(&mut self, font: &Font, key: GlyphKey) {
+        if self.entries.contains_key(&key) {
+            return;
+        }
+
+        let Some(bitmap) = font.rasterize(key) else {
+            return;
+        };
+
+        let Some((x, y)) = self.allocate(bitmap.width, bitmap.height) else {
+            warn!("Glyph {:?} does not fit in the glyph atlas", key);
+            return;
+        };
+
+        for row in 0..bitmap.height {
+            for col in 0..bitmap.width {
+                let alpha = bitmap.coverage[(row * bitmap.width + col) as usize];
+                self.image.put_pixel(x + col, y + row, Rgba([255, 255, 255, alpha]));
+            }
+        }
+
+        self.entries.insert(key, [x, y, bitmap.width, bitmap.height]);
+        self.mark_dirty([x, y, bitmap.width, bitmap.height]);
+    }
+
+    /// Returns the normalized texture coordinates (min u, min v, max u, max v) of the glyph.
+    pub fn uv_rect(&self, key: &GlyphKey) -> Option<[f32; 4]> {
+        let [x, y, w, h] = self.entries.get(key)?;
+        let (width, height) = (self.image.width() as f32, self.image.height() as f32);
+        Some([
+            *x as f32 / width,
+            *y as f32 / height,
+            (x + w) as f32 / width,
+            (y + h) as f32 / height,
+        ])
+    }
+
+    /// Returns the atlas image and the region of it that changed since the last call, if any did.
+    pub fn take_changes(&mut self) -> Option<(&RgbaImage, [u32; 4])> {
+        self.dirty.take().map(|region| (&self.image, region))
+    }
+
+    /// Grows the changed region to include the rectangle given as x, y, width and height.
+    fn mark_dirty(&mut self, [x, y, width, height]: [u32; 4]) {
+        self.dirty = Some(match self.dirty {
+            Some([dx, dy, dw, dh]) => {
+                let (left, top) = (x.min(dx), y.min(dy));
+                let (right, bottom) = ((x + width).max(dx + dw), (y + height).max(dy + dh));
+                [left, top, right - left, bottom - top]
+            }
+            None => [x, y, width, height],
+        });
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.dirty = Some([0, 0, self.image.width(), self.image.height()]);
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width + 2 * PADDING > MAX_SIZE || height + 2 * PADDING > MAX_SIZE {
+            return None;
+        }
+
+        loop {
+            // Start a new shelf when the current one is full
+            if self.cursor_x + width + PADDING > self.image.width() {
+                self.cursor_x = PADDING;
+                self.cursor_y += self.row_height + PADDING;
+                self.row_height = 0;
+            }
+
+            if self.cursor_y + height + PADDING <= self.image.height() {
+                let position = (self.cursor_x, self.cursor_y);
+                self.cursor_x += width + PADDING;
+                self.row_height = self.row_height.max(height);
+                return Some(position);
+            }
+
+            if self.image.width() < MAX_SIZE {
+                self.grow();
+            } else if !self.cleared {
+                warn!("Glyph atlas is full, clearing it");
+                self.clear();
+            } else {
+                // The frame alone needs more glyphs than fit, clearing again would drop glyphs it already uses
+                return None;
+            }
+        }
+    }
+
+    /// Doubles the atlas size, keeping existing glyphs at their pixel positions.
+    fn grow(&mut self) {
+        let size = self.image.width() * 2;
+        let mut image = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 0]));
+        image::imageops::replace(&mut image, &self.image, 0, 0);
+        self.image = image;
+        self.mark_all_dirty();
+
+        // Continue packing below the existing shelves, which now span only part of the width
+        self.cursor_x = PADDING;
+        self.cursor_y += self.row_height + PADDING;
+        self.row_height = 0;
+    }
+
+    fn clear(&mut self) {
+        self.image.pixels_mut().for_each(|p| *p = Rgba([255, 255, 255, 0]));
+        self.entries.clear();
+        self.cursor_x = PADDING;
+        self.cursor_y = PADDING;
+        self.row_height = 0;
+        self.cleared = true;
+        self.mark_all_dirty();
+    }
+
+}
@@ -1,4 +1,10 @@
-use crate::renderer::vertex::Vertex2D;
+use crate::graphics::stroke::Stroke;
+use crate::renderer::tessellation::{self, TriangleBuilder};
+use crate::renderer::vertex::{Vertex2D, Vertex3D};
+use cgmath::{ElementWise, EuclideanSpace, InnerSpace, Point2, Vector2, Vector3};
+use log::warn;
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
 #[derive(Clone, Debug)]
 pub struct Mesh<V> {
@@ -114,4 +120,420 @@ impl MeshBuilder2D {
         }
     }
 
-}
\ No newline at end of file
+    // Creates a mesh covering the outline of the line through the points, stroked with the given joins and caps.
+    pub fn from_polyline(points: &[Point2<f32>], closed: bool, stroke: &Stroke, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let points: Vec<_> = points.iter().map(|p| p.to_vec()).collect();
+        let mut builder = TriangleBuilder::new();
+        tessellation::stroke_polyline(&mut builder, &points, closed, stroke);
+        builder.build(color)
+    }
+
+    // Creates a mesh filling a simple, possibly concave polygon with holes, with UVs spanning its bounding box.
+    pub fn from_polygon(points: &[Point2<f32>], holes: &[Vec<Point2<f32>>], color: [f32; 4]) -> Mesh<Vertex2D> {
+        let outline: Vec<_> = points.iter().map(|p| p.to_vec()).collect();
+        let holes: Vec<Vec<_>> = holes.iter().map(|hole| hole.iter().map(|p| p.to_vec()).collect()).collect();
+        let mut builder = TriangleBuilder::new();
+        tessellation::triangulate_polygon(&mut builder, &outline, &holes);
+        builder.build(color)
+    }
+
+    // Creates a mesh from an ellipse centered on the origin.
+    pub fn from_ellipse(radius_x: f32, radius_y: f32, segments: usize, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let points = tessellation::ellipse_points(radius_x, radius_y, 0.0, TAU, segments);
+        let mut builder = TriangleBuilder::new();
+        tessellation::fill_fan(&mut builder, Vector2::new(0.0, 0.0), &points, false);
+        builder.build(color)
+    }
+
+    // Creates a mesh from a rectangle with rounded corners, with radii for the top-left, top-right, bottom-right and bottom-left corners.
+    pub fn from_rounded_rectangle(width: f32, height: f32, radii: [f32; 4], segments_per_corner: usize, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let points = tessellation::rounded_rectangle_points(width, height, radii, segments_per_corner);
+        let mut builder = TriangleBuilder::new();
+        tessellation::fill_fan(&mut builder, Vector2::new(0.0, 0.0), &points, true);
+        builder.build(color)
+    }
+
+    // Creates a mesh from a ring between two radii.
+    pub fn from_ring(inner_radius: f32, outer_radius: f32, segments: usize, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let inner = tessellation::ellipse_points(inner_radius, inner_radius, 0.0, TAU, segments);
+        let outer = tessellation::ellipse_points(outer_radius, outer_radius, 0.0, TAU, segments);
+        let mut builder = TriangleBuilder::new();
+        tessellation::fill_strip(&mut builder, &inner, &outer);
+        builder.build(color)
+    }
+
+    // Creates a mesh from a pie slice between two angles in degrees, counter-clockwise from the x axis.
+    pub fn from_pie(radius: f32, start_angle: f32, end_angle: f32, segments: usize, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let points = tessellation::ellipse_points(radius, radius, start_angle.to_radians(), end_angle.to_radians(), segments);
+        let mut builder = TriangleBuilder::new();
+        tessellation::fill_fan(&mut builder, Vector2::new(0.0, 0.0), &points, false);
+        builder.build(color)
+    }
+
+    // Creates a mesh from a stroked circular arc between two angles in degrees, counter-clockwise from the x axis.
+    pub fn from_arc(radius: f32, start_angle: f32, end_angle: f32, segments: usize, stroke: &Stroke, color: [f32; 4]) -> Mesh<Vertex2D> {
+        let points = tessellation::ellipse_points(radius, radius, start_angle.to_radians(), end_angle.to_radians(), segments);
+        let closed = (end_angle - start_angle).abs() >= 360.0;
+        let mut builder = TriangleBuilder::new();
+        tessellation::stroke_polyline(&mut builder, &points, closed, stroke);
+        builder.build(color)
+    }
+
+}
+
+pub struct MeshBuilder3D;
+
+impl MeshBuilder3D {
+
+    // Creates a box centered on the origin, with each face mapping the whole texture.
+    pub fn from_cube(width: f32, height: f32, depth: f32) -> Mesh<Vertex3D> {
+        let half = Vector3::new(width, height, depth) / 2.0;
+        let faces = [
+            (Vector3::unit_x(), -Vector3::unit_z(), -Vector3::unit_y()),
+            (-Vector3::unit_x(), Vector3::unit_z(), -Vector3::unit_y()),
+            (Vector3::unit_y(), Vector3::unit_x(), Vector3::unit_z()),
+            (-Vector3::unit_y(), Vector3::unit_x(), -Vector3::unit_z()),
+            (Vector3::unit_z(), Vector3::unit_x(), -Vector3::unit_y()),
+            (-Vector3::unit_z(), -Vector3::unit_x(), -Vector3::unit_y()),
+        ];
+        let mut builder = SurfaceBuilder::default();
+        for (normal, right, down) in faces {
+            let scale = |axis: Vector3<f32>| axis.mul_element_wise(half);
+            builder.plane(scale(normal), scale(right), scale(down), normal, 1, 1);
+        }
+        builder.build()
+    }
+
+    // Creates a plane on the xz axes facing up, split into a grid of subdivisions along each axis.
+    pub fn from_plane(width: f32, depth: f32, subdivisions_x: usize, subdivisions_z: usize) -> Mesh<Vertex3D> {
+        let mut builder = SurfaceBuilder::default();
+        let right = Vector3::unit_x() * width / 2.0;
+        let down = Vector3::unit_z() * depth / 2.0;
+        builder.plane(Vector3::new(0.0, 0.0, 0.0), right, down, Vector3::unit_y(), subdivisions_x, subdivisions_z);
+        builder.build()
+    }
+
+    // Creates a sphere from rings of latitude and segments of longitude, with the texture wrapped around it.
+    pub fn from_uv_sphere(radius: f32, segments: usize, rings: usize) -> Mesh<Vertex3D> {
+        let rings = rings.max(2);
+        let profile: Vec<_> = (0..=rings)
+            .map(|j| {
+                let angle = PI * j as f32 / rings as f32;
+                ProfilePoint::new(radius * angle.sin(), radius * angle.cos(), Vector2::new(angle.sin(), angle.cos()), j as f32 / rings as f32)
+            })
+            .collect();
+        let mut builder = SurfaceBuilder::default();
+        builder.lathe(&profile, segments);
+        builder.build()
+    }
+
+    // Creates a sphere by subdividing an icosahedron, giving evenly sized triangles. Six subdivisions are the most 16-bit indices allow.
+    pub fn from_icosphere(radius: f32, subdivisions: usize) -> Mesh<Vertex3D> {
+        let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let mut points: Vec<Vector3<f32>> = [
+            [-1.0, t, 0.0], [1.0, t, 0.0], [-1.0, -t, 0.0], [1.0, -t, 0.0],
+            [0.0, -1.0, t], [0.0, 1.0, t], [0.0, -1.0, -t], [0.0, 1.0, -t],
+            [t, 0.0, -1.0], [t, 0.0, 1.0], [-t, 0.0, -1.0], [-t, 0.0, 1.0],
+        ].iter().map(|p| Vector3::from(*p).normalize()).collect();
+        let mut faces = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: usize, b: usize| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    points.push((points[a] + points[b]).normalize());
+                    points.len() - 1
+                })
+            };
+            faces = faces.iter()
+                .flat_map(|&[a, b, c]| {
+                    let [ab, bc, ca] = [midpoint(a, b), midpoint(b, c), midpoint(c, a)];
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+            // Every point is at least one vertex, so further levels cannot be indexed either, which building the mesh reports
+            if points.len() > u16::MAX as usize + 1 {
+                break;
+            }
+        }
+
+        let mut builder = SurfaceBuilder::default();
+        for face in faces {
+            builder.sphere_face(face.map(|i| points[i]), radius);
+        }
+        builder.build()
+    }
+
+    // Creates a cylinder along the y axis, centered on the origin and closed at both ends.
+    pub fn from_cylinder(radius: f32, height: f32, segments: usize) -> Mesh<Vertex3D> {
+        let half = height / 2.0;
+        let profile = [
+            ProfilePoint::new(radius, half, Vector2::new(1.0, 0.0), 0.0),
+            ProfilePoint::new(radius, -half, Vector2::new(1.0, 0.0), 1.0),
+        ];
+        let mut builder = SurfaceBuilder::default();
+        builder.lathe(&profile, segments);
+        builder.disk(half, radius, segments, true);
+        builder.disk(-half, radius, segments, false);
+        builder.build()
+    }
+
+    // Creates a cone along the y axis pointing up, centered on the origin and closed at the base.
+    pub fn from_cone(radius: f32, height: f32, segments: usize) -> Mesh<Vertex3D> {
+        let half = height / 2.0;
+        let normal = Vector2::new(height, radius).normalize();
+        let profile = [
+            ProfilePoint::new(0.0, half, normal, 0.0),
+            ProfilePoint::new(radius, -half, normal, 1.0),
+        ];
+        let mut builder = SurfaceBuilder::default();
+        builder.lathe(&profile, segments);
+        builder.disk(-half, radius, segments, false);
+        builder.build()
+    }
+
+    // Creates a capsule along the y axis with the given total height, made of a cylinder between two hemispheres.
+    pub fn from_capsule(radius: f32, height: f32, segments: usize, rings: usize) -> Mesh<Vertex3D> {
+        let rings = rings.max(1);
+        let half = (height / 2.0 - radius).max(0.0);
+        let mut profile = Vec::with_capacity(rings * 2 + 2);
+        for (center, start) in [(half, 0.0), (-half, FRAC_PI_2)] {
+            for j in 0..=rings {
+                let angle = start + FRAC_PI_2 * j as f32 / rings as f32;
+                profile.push(ProfilePoint::new(radius * angle.sin(), center + radius * angle.cos(), Vector2::new(angle.sin(), angle.cos()), 0.0));
+            }
+        }
+
+        // Spread the texture by distance along the outline so it is not stretched over the cylinder
+        let length = PI * radius + 2.0 * half;
+        let mut distance = 0.0;
+        for j in 1..profile.len() {
+            distance += (profile[j].radius - profile[j - 1].radius).hypot(profile[j].y - profile[j - 1].y);
+            profile[j].v = if length > 0.0 { distance / length } else { 0.0 };
+        }
+
+        let mut builder = SurfaceBuilder::default();
+        builder.lathe(&profile, segments);
+        builder.build()
+    }
+
+    // Creates a torus around the y axis, with the texture wrapped around both circles.
+    pub fn from_torus(major_radius: f32, minor_radius: f32, major_segments: usize, minor_segments: usize) -> Mesh<Vertex3D> {
+        let minor_segments = minor_segments.max(3);
+        let profile: Vec<_> = (0..=minor_segments)
+            .map(|j| {
+                let angle = TAU * j as f32 / minor_segments as f32;
+                let normal = Vector2::new(angle.cos(), angle.sin());
+                ProfilePoint::new(major_radius + minor_radius * normal.x, minor_radius * normal.y, normal, j as f32 / minor_segments as f32)
+            })
+            .collect();
+        let mut builder = SurfaceBuilder::default();
+        builder.lathe(&profile, major_segments);
+        builder.build()
+    }
+
+}
+
+const SEAM_EPSILON: f32 = 1e-6;
+
+/// Clips a polygon on the unit sphere to the side of the x = 0 plane with the given sign, projecting new points onto the sphere.
+fn clip_to_side(polygon: &[Vector3<f32>], side: f32) -> Vec<Vector3<f32>> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if a.x * side >= -SEAM_EPSILON {
+            clipped.push(a);
+        }
+        if a.x * side > SEAM_EPSILON && b.x * side < -SEAM_EPSILON || a.x * side < -SEAM_EPSILON && b.x * side > SEAM_EPSILON {
+            // Measured from the same end whichever way round the edge is, so faces on both sides of it agree on the point
+            let (a, b) = if a.x > 0.0 { (a, b) } else { (b, a) };
+            let mut crossing = (a + (b - a) * (a.x / (a.x - b.x))).normalize();
+            crossing.x = 0.0;
+            clipped.push(crossing);
+        }
+    }
+    clipped
+}
+
+/// A point on the outline of a surface of revolution, with its distance from the axis and its normal on the radial plane.
+struct ProfilePoint {
+    radius: f32,
+    y: f32,
+    normal: Vector2<f32>,
+    v: f32,
+}
+
+impl ProfilePoint {
+    fn new(radius: f32, y: f32, normal: Vector2<f32>, v: f32) -> Self {
+        Self { radius, y, normal, v }
+    }
+}
+
+/// Collects vertices and triangles for 3D meshes, winding each triangle counter-clockwise around its normals.
+#[derive(Default)]
+struct SurfaceBuilder {
+    vertices: Vec<Vertex3D>,
+    indices: Vec<u32>,
+    /// Vertices added with [`SurfaceBuilder::shared_vertex`], by the bits of their position, normal and texture coordinates.
+    shared: HashMap<[u32; 8], u32>,
+}
+
+impl SurfaceBuilder {
+
+    fn vertex(&mut self, position: Vector3<f32>, normal: Vector3<f32>, uv: [f32; 2]) -> u32 {
+        self.vertices.push(Vertex3D { position: position.into(), normal: normal.into(), uv });
+        self.vertices.len() as u32 - 1
+    }
+
+    /// Adds a vertex unless an identical one was already added this way, so neighbouring faces can share it.
+    fn shared_vertex(&mut self, position: Vector3<f32>, normal: Vector3<f32>, uv: [f32; 2]) -> u32 {
+        let key = [position.x, position.y, position.z, normal.x, normal.y, normal.z, uv[0], uv[1]].map(f32::to_bits);
+        if let Some(&index) = self.shared.get(&key) {
+            return index;
+        }
+        let index = self.vertex(position, normal, uv);
+        self.shared.insert(key, index);
+        index
+    }
+
+    /// Adds a triangle between existing vertices, skipping degenerate ones and flipping it to face along its normals.
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        let [va, vb, vc] = [a, b, c].map(|i| self.vertices[i as usize]);
+        let [pa, pb, pc] = [va, vb, vc].map(|v| Vector3::from(v.position));
+        let face = (pb - pa).cross(pc - pa);
+        if face.magnitude2() <= f32::EPSILON * f32::EPSILON {
+            return;
+        }
+        let normal = Vector3::from(va.normal) + Vector3::from(vb.normal) + Vector3::from(vc.normal);
+        if face.dot(normal) >= 0.0 {
+            self.indices.extend_from_slice(&[a, b, c]);
+        } else {
+            self.indices.extend_from_slice(&[a, c, b]);
+        }
+    }
+
+    /// Adds a grid of `columns` by `rows` quads, with UVs running along the columns and down the rows.
+    fn grid(&mut self, columns: usize, rows: usize, vertex: impl Fn(usize, usize) -> (Vector3<f32>, Vector3<f32>)) {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let start = self.vertices.len() as u32;
+        for j in 0..=rows {
+            for i in 0..=columns {
+                let (position, normal) = vertex(i, j);
+                self.vertex(position, normal, [i as f32 / columns as f32, j as f32 / rows as f32]);
+            }
+        }
+        let index = |i: usize, j: usize| start + (j * (columns + 1) + i) as u32;
+        for j in 0..rows {
+            for i in 0..columns {
+                self.triangle(index(i, j), index(i, j + 1), index(i + 1, j + 1));
+                self.triangle(index(i, j), index(i + 1, j + 1), index(i + 1, j));
+            }
+        }
+    }
+
+    /// Adds a flat rectangle around the center, spanning `right` and `down` in each direction.
+    fn plane(&mut self, center: Vector3<f32>, right: Vector3<f32>, down: Vector3<f32>, normal: Vector3<f32>, columns: usize, rows: usize) {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        self.grid(columns, rows, |i, j| {
+            let u = i as f32 / columns as f32 * 2.0 - 1.0;
+            let v = j as f32 / rows as f32 * 2.0 - 1.0;
+            (center + right * u + down * v, normal)
+        });
+    }
+
+    /// Adds the surface swept by rotating the profile around the y axis, with the texture seam at the back.
+    fn lathe(&mut self, profile: &[ProfilePoint], segments: usize) {
+        let segments = segments.max(3);
+        let start = self.vertices.len();
+        self.grid(segments, profile.len() - 1, |i, j| {
+            let angle = (i as f32 / segments as f32 - 0.5) * TAU;
+            let (sin, cos) = angle.sin_cos();
+            let point = &profile[j];
+            let position = Vector3::new(point.radius * sin, point.y, point.radius * cos);
+            let normal = Vector3::new(point.normal.x * sin, point.normal.y, point.normal.x * cos);
+            (position, normal)
+        });
+        for (j, row) in self.vertices[start..].chunks_mut(segments + 1).enumerate() {
+            for vertex in row {
+                vertex.uv[1] = profile[j].v;
+            }
+        }
+    }
+
+    /// Adds a triangle on the unit sphere scaled by the radius, split where it crosses the texture seam at the back.
+    /// Vertices are shared with neighbouring faces, except along the seam and at the poles where their texture coordinates differ.
+    fn sphere_face(&mut self, directions: [Vector3<f32>; 3], radius: f32) {
+        let crosses_seam = (0..3).any(|i| {
+            let (a, b) = (directions[i], directions[(i + 1) % 3]);
+            (a.x > SEAM_EPSILON && b.x < -SEAM_EPSILON || a.x < -SEAM_EPSILON && b.x > SEAM_EPSILON)
+                && (a + (b - a) * (a.x / (a.x - b.x))).z < 0.0
+        });
+        let sides = if crosses_seam {
+            vec![(clip_to_side(&directions, 1.0), 1.0), (clip_to_side(&directions, -1.0), -1.0)]
+        } else {
+            let side = if directions.iter().map(|d| d.x).sum::<f32>() < 0.0 { -1.0 } else { 1.0 };
+            vec![(directions.to_vec(), side)]
+        };
+
+        for (polygon, side) in sides {
+            let mut uvs: Vec<_> = polygon.iter()
+                .map(|d| {
+                    // Vertices on the seam belong to whichever edge of the texture the polygon lies against
+                    let u = if d.x.abs() <= SEAM_EPSILON && d.z < 0.0 { (side + 1.0) / 2.0 } else { 0.5 + d.x.atan2(d.z) / TAU };
+                    [u, d.y.clamp(-1.0, 1.0).acos() / PI]
+                })
+                .collect();
+
+            // Longitude is undefined at the poles, so take it from the rest of the polygon
+            let pole = |d: &Vector3<f32>| d.x.abs() <= SEAM_EPSILON && d.z.abs() <= SEAM_EPSILON;
+            let others: Vec<_> = polygon.iter().zip(&uvs).filter(|(d, _)| !pole(d)).map(|(_, uv)| uv[0]).collect();
+            let average = others.iter().sum::<f32>() / others.len().max(1) as f32;
+            for (d, uv) in polygon.iter().zip(uvs.iter_mut()) {
+                if pole(d) {
+                    uv[0] = average;
+                }
+            }
+
+            let indices: Vec<_> = polygon.iter().zip(uvs).map(|(d, uv)| self.shared_vertex(d * radius, *d, uv)).collect();
+            for i in 1..indices.len() - 1 {
+                self.triangle(indices[0], indices[i], indices[i + 1]);
+            }
+        }
+    }
+
+    /// Adds a flat disk on the xz axes facing up or down, with the texture mapped as seen from that side.
+    fn disk(&mut self, y: f32, radius: f32, segments: usize, up: bool) {
+        let segments = segments.max(3);
+        let normal = if up { Vector3::unit_y() } else { -Vector3::unit_y() };
+        let facing = if up { -1.0 } else { 1.0 };
+        let center = self.vertex(Vector3::new(0.0, y, 0.0), normal, [0.5, 0.5]);
+        let rim: Vec<_> = (0..segments)
+            .map(|i| {
+                let angle = TAU * i as f32 / segments as f32;
+                let (sin, cos) = angle.sin_cos();
+                let uv = [0.5 + sin / 2.0, 0.5 - facing * cos / 2.0];
+                self.vertex(Vector3::new(radius * sin, y, radius * cos), normal, uv)
+            })
+            .collect();
+        for i in 0..segments {
+            self.triangle(center, rim[i], rim[(i + 1) % segments]);
+        }
+    }
+
+    fn build(self) -> Mesh<Vertex3D> {
+        if self.vertices.len() > u16::MAX as usize + 1 {
+            warn!("Mesh needs {} vertices, more than 16-bit indices allow", self.vertices.len());
+            return Mesh { vertices: Vec::new(), indices: Vec::new() };
+        }
+
+        Mesh {
+            vertices: self.vertices,
+            indices: self.indices.into_iter().map(|i| i as u16).collect(),
+        }
+    }
+}
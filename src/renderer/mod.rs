@@ -3,4 +3,8 @@ pub mod vertex;
 mod uniform;
 pub mod state;
 pub mod mesh;
-mod texture;
\ No newline at end of file
+mod texture;
+mod batch;
+mod arena;
+mod glyph_atlas;
+pub mod tessellation;
@@ -1,11 +1,15 @@
-use wgpu::{BindGroupLayout, Device, PrimitiveTopology, ShaderModule, TextureFormat, VertexBufferLayout};
+use wgpu::{BindGroupLayout, BlendState, ColorWrites, DepthStencilState, Device, Face, FrontFace, PrimitiveTopology, ShaderModule, TextureFormat, VertexBufferLayout};
 
 pub struct PipelineBuilder<'a> {
     label: Option<&'a str>,
     vertex_shader: Option<&'a ShaderModule>,
     fragment_shader: Option<&'a ShaderModule>,
     vertex_buffer_layouts: Vec<VertexBufferLayout<'a>>,
-    primitive_topology: PrimitiveTopology,
+    blend_state: BlendState,
+    front_face: FrontFace,
+    cull_mode: Option<Face>,
+    color_writes: ColorWrites,
+    depth_stencil: Option<DepthStencilState>,
 }
 
 impl<'a> PipelineBuilder<'a> {
@@ -16,7 +20,11 @@ impl<'a> PipelineBuilder<'a> {
             vertex_shader: None,
             fragment_shader: None,
             vertex_buffer_layouts: Vec::new(),
-            primitive_topology: PrimitiveTopology::TriangleList,
+            blend_state: BlendState::REPLACE,
+            front_face: FrontFace::Cw,
+            cull_mode: Some(Face::Back),
+            color_writes: ColorWrites::ALL,
+            depth_stencil: None,
         }
     }
 
@@ -40,11 +48,30 @@ impl<'a> PipelineBuilder<'a> {
         self
     }
 
-    pub fn with_primitive_topology(mut self, topology: PrimitiveTopology) -> Self {
-        self.primitive_topology = topology;
+    pub fn with_blend_state(mut self, blend_state: BlendState) -> Self {
+        self.blend_state = blend_state;
         self
     }
 
+    pub fn with_front_face(mut self, front_face: FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    pub fn with_cull_mode(mut self, cull_mode: Option<Face>) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_color_writes(mut self, color_writes: ColorWrites) -> Self {
+        self.color_writes = color_writes;
+        self
+    }
+
+    pub fn with_depth_stencil(mut self, depth_stencil: DepthStencilState) -> Self {
+        self.depth_stencil = Some(depth_stencil);
+        self
+    }
 
     pub fn build(self, device: &Device, surface_format: TextureFormat, bind_group_layouts: &[&BindGroupLayout]) -> Result<wgpu::RenderPipeline, String> {
 
@@ -58,38 +85,35 @@ impl<'a> PipelineBuilder<'a> {
         });
 
         Ok(device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
+            label: self.label,
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &vertex_shader,
+                module: vertex_shader,
                 entry_point: Some("vs_main"),
                 compilation_options: Default::default(),
                 buffers: &self.vertex_buffer_layouts,
             },
             fragment: Some(wgpu::FragmentState {
-                module: &fragment_shader,
+                module: fragment_shader,
                 entry_point: Some("fs_main"),
                 compilation_options: Default::default(),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: surface_format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent::REPLACE,
-                        alpha: wgpu::BlendComponent::REPLACE,
-                    }),
-                    write_mask: wgpu::ColorWrites::ALL,
+                    blend: Some(self.blend_state),
+                    write_mask: self.color_writes,
                 })],
             }),
             multiview_mask: None,
             primitive: wgpu::PrimitiveState {
-                topology: self.primitive_topology,
+                topology: PrimitiveTopology::TriangleList,
                 strip_index_format: None,
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: Some(wgpu::Face::Back),
+                front_face: self.front_face,
+                cull_mode: self.cull_mode,
                 unclipped_depth: false,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: self.depth_stencil,
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
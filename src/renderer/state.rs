@@ -1,49 +1,272 @@
+use crate::graphics::blend::BlendMode;
 use crate::graphics::color::Color;
+use crate::graphics::font::{Font, GlyphQuad};
 use crate::graphics::image::Image;
-use crate::graphics::transform::Transform;
-use crate::prelude::{Canvas, DrawStyle};
+use crate::graphics::light::Lighting;
+use crate::graphics::material::{read_shader, Material, Material3D, MAX_MATERIAL_TEXTURES};
+use crate::graphics::post_effect::PostEffect;
+use crate::graphics::render_target::RenderTarget;
+use crate::prelude::{Canvas, ClipRect, DrawStyle, MaskMode};
+use crate::renderer::arena::BufferArena;
+use crate::renderer::batch::{build_batches, BatchTexture, DrawBatch, StencilMode};
+use crate::renderer::glyph_atlas::GlyphAtlas;
 use crate::renderer::pipeline::PipelineBuilder;
 use crate::renderer::texture::Texture;
-use crate::renderer::uniform::Uniform2D;
-use crate::renderer::vertex::Vertex2D;
+use crate::renderer::uniform::{MaterialUniform, PostUniform, Uniform2D, Uniform3D};
+use crate::renderer::vertex::{InstanceData, Vertex2D, Vertex3D};
 use crate::renderer::mesh::Mesh;
+use cgmath::Matrix4;
+use log::{debug, info, warn};
 use pollster::FutureExt;
+use image::RgbaImage;
 use std::cmp::max;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::SystemTime;
 use std::collections::HashMap;
-use wgpu::util::DeviceExt;
-use wgpu::{Adapter, AdapterInfo, BindGroup, BindGroupLayout, Buffer, Device, Instance, PresentMode, Queue, Surface};
+use wgpu::{Adapter, AdapterInfo, BindGroup, BindGroupLayout, Buffer, Device, Instance, PresentMode, Queue, RenderPipeline, ShaderModule, Surface, TextureFormat, TextureView};
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
-const MAX_INSTANCES: usize = 1000; //
+/// Number of draws the uniform buffer has room for before it needs to grow.
+const INITIAL_UNIFORM_CAPACITY: usize = 1000;
+
+/// Format of the depth-stencil attachment, whose stencil holds the mask level of every pixel.
+const DEPTH_STENCIL_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
+
+/// Declarations shared by every material shader, prepended to the material's own source.
+const MATERIAL_PRELUDE: &str = include_str!("../shaders/material_common.wgsl");
+
+/// Declarations shared by every post effect shader, prepended to the effect's own source.
+const POST_PRELUDE: &str = include_str!("../shaders/post/common.wgsl");
+
+/// Number of post effects the post uniform buffer has room for before it needs to grow.
+const INITIAL_POST_UNIFORM_CAPACITY: usize = 8;
+
+/// Initial size in bytes of the vertex and index arenas.
+const INITIAL_VERTEX_ARENA_SIZE: u64 = 256 * 1024;
+const INITIAL_INDEX_ARENA_SIZE: u64 = 64 * 1024;
 
 #[derive(Clone, Debug)]
 pub enum DrawCommand {
     DrawMesh2D {
         mesh: Mesh<Vertex2D>,
         camera_matrix: [[f32; 4]; 4],
-        transform: Transform,
+        transform: Matrix4<f32>,
         style: DrawStyle
+    },
+    DrawInstanced2D {
+        mesh: Mesh<Vertex2D>,
+        camera_matrix: [[f32; 4]; 4],
+        instances: Vec<InstanceData>,
+        style: DrawStyle
+    },
+    DrawText {
+        glyphs: Vec<GlyphQuad>,
+        font: Font,
+        camera_matrix: [[f32; 4]; 4],
+        transform: Matrix4<f32>,
+        style: DrawStyle
+    },
+    DrawMesh3D {
+        mesh: Mesh<Vertex3D>,
+        camera_matrix: [[f32; 4]; 4],
+        transform: Matrix4<f32>,
+        material: Material3D,
+        lighting: Option<Lighting>
+    },
+    /// Clips the draws that follow to a rectangle in screen pixels, or stops clipping.
+    SetClipRect(Option<ClipRect>),
+    /// Restricts the draws that follow to the inside or outside of the mesh, within any masks already pushed.
+    PushMask {
+        mesh: Mesh<Vertex2D>,
+        camera_matrix: [[f32; 4]; 4],
+        transform: Matrix4<f32>,
+        mode: MaskMode
+    },
+    /// Removes the most recently pushed mask.
+    PopMask,
+    /// Draws the commands into the render target before the frame they are part of.
+    RenderToTarget {
+        target: RenderTarget,
+        commands: Vec<DrawCommand>,
+        bg_color: Color
     }
 }
 
+impl DrawCommand {
+
+    /// Returns the images the command samples, including those of its material.
+    pub(crate) fn images(&self) -> impl Iterator<Item = &Image> {
+        let (style, material_3d) = match self {
+            DrawCommand::DrawMesh2D { style, .. } | DrawCommand::DrawInstanced2D { style, .. } => (Some(style), None),
+            DrawCommand::DrawMesh3D { material, .. } => (None, Some(material)),
+            _ => (None, None),
+        };
+        style.into_iter()
+            .flat_map(|style| style.image.iter().chain(style.material.iter().flat_map(|material| &material.textures)))
+            .chain(material_3d.into_iter().flat_map(|material| &material.image))
+    }
+
+}
+
+/// The commands drawn into one color attachment during a frame.
+struct FramePass<'a> {
+    /// The render target drawn into, or `None` for the frame itself.
+    target: Option<&'a RenderTarget>,
+    commands: &'a [DrawCommand],
+    bg_color: Color,
+}
+
+impl<'a> FramePass<'a> {
+
+    /// Collects the passes needed to draw the commands, render targets first so their images are ready when sampled.
+    fn collect(commands: &'a [DrawCommand], bg_color: Color, target: Option<&'a RenderTarget>, passes: &mut Vec<FramePass<'a>>) {
+        for command in commands {
+            if let DrawCommand::RenderToTarget { target, commands, bg_color } = command {
+                Self::collect(commands, *bg_color, Some(target), passes);
+            }
+        }
+        passes.push(FramePass { target, commands, bg_color });
+    }
+
+}
+
+/// Where the frames produced by a [`RenderState`] end up.
+#[derive(Debug)]
+enum FrameTarget {
+    /// A window surface, presented after every frame.
+    Surface {
+        surface: Surface<'static>,
+        config: wgpu::SurfaceConfiguration,
+    },
+    /// An offscreen texture that can be read back after rendering.
+    Offscreen {
+        texture: wgpu::Texture,
+        format: TextureFormat,
+    },
+}
+
+/// Statistics about the last rendered frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    /// Number of draw commands submitted by the canvas.
+    pub draw_commands: usize,
+    /// Number of draw calls issued to the GPU after batching.
+    pub draw_calls: usize,
+    /// Number of triangles drawn for the frame, counting every instance.
+    pub triangles: usize,
+    /// Number of draws the uniform buffer currently has room for.
+    pub uniform_capacity: usize,
+    /// Total number of times the uniform buffer had to grow since creation.
+    pub uniform_reallocations: usize,
+    /// Bytes of uniform, vertex and index data uploaded for the frame.
+    pub bytes_uploaded: u64,
+    /// Current size in bytes of the vertex arena.
+    pub vertex_arena_capacity: u64,
+    /// Current size in bytes of the index arena.
+    pub index_arena_capacity: u64,
+    /// Total number of times the vertex and index arenas had to grow since creation.
+    pub arena_reallocations: usize,
+    /// Number of render targets with textures on the GPU.
+    pub render_targets: usize,
+    /// Bytes of image and glyph atlas pixels uploaded for the frame.
+    pub texture_bytes_uploaded: u64,
+}
+
+/// What the renderer keeps of a render target besides its color texture, which lives in the texture cache.
+#[derive(Debug)]
+struct RenderTargetTextures {
+    depth_stencil: TextureView,
+    /// Stops upgrading once the render target has been dropped, so its textures can be released.
+    handle: Weak<u64>,
+}
+
+/// Identifies one of the cached 2D pipelines.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    blend_mode: BlendMode,
+    instanced: bool,
+    stencil: StencilMode,
+    mesh_3d: bool,
+}
+
+impl PipelineKey {
+    fn for_batch(batch: &DrawBatch) -> Self {
+        Self {
+            blend_mode: batch.blend_mode,
+            instanced: batch.is_instanced(),
+            stencil: batch.stencil,
+            mesh_3d: batch.is_3d(),
+        }
+    }
+}
+
+/// Compiled shaders by file, or an empty string for shaders not loaded from a file, then by the source they were compiled from.
+/// Hot reload recompiles every source loaded from a changed file, so materials and effects holding older sources pick up the change.
+type ShaderCache<T> = HashMap<String, HashMap<String, T>>;
+
+/// A compiled material shader and its pipelines, one per blend mode it was drawn with.
+#[derive(Debug)]
+struct MaterialShader {
+    /// `None` if the shader failed to compile.
+    module: Option<ShaderModule>,
+    /// Pipelines by blend mode, `None` if the pipeline could not be created.
+    pipelines: HashMap<BlendMode, Option<RenderPipeline>>,
+}
+
 #[derive(Debug)]
 pub struct RenderState {
-    surface: Surface<'static>,
+    target: FrameTarget,
     adapter: Adapter,
     device: Device,
     queue: Queue,
-    config: wgpu::SurfaceConfiguration,
-    render_pipeline: wgpu::RenderPipeline,
+    format: TextureFormat,
+    shader2d: ShaderModule,
+    shader2d_instanced: ShaderModule,
+    shader3d: ShaderModule,
+    pipelines: HashMap<PipelineKey, RenderPipeline>,
     size: PhysicalSize<u32>,
+    depth_stencil_view: TextureView,
     uniform2d: Uniform2D,
     uniform_buffer: Buffer,
     uniform_bind_group: BindGroup,
+    uniform_bind_group_layout: BindGroupLayout,
+    vertex_arena: BufferArena,
+    index_arena: BufferArena,
+    instance_arena: BufferArena,
     texture_bind_group_layout: BindGroupLayout,
+    /// Textures by image path, including the color textures of render targets, which only exist on the GPU.
     texture_cache: HashMap<String, (Texture, BindGroup)>,
+    /// Render targets with textures on the GPU, by the same key as their color textures.
+    render_targets: HashMap<String, RenderTargetTextures>,
+    glyph_atlas: GlyphAtlas,
+    /// Texture of the glyph atlas, uploaded once text has been drawn.
+    glyph_atlas_texture: Option<(Texture, BindGroup)>,
     default_white_texture: (Texture, BindGroup),
-    padded_uniform_size: u64, 
+    padded_uniform_size: u64,
+    uniform_3d_layout: BindGroupLayout,
+    uniform_3d_buffer: Buffer,
+    uniform_3d_bind_group: BindGroup,
+    padded_uniform_3d_size: u64,
+    material_uniform_layout: BindGroupLayout,
+    material_texture_layout: BindGroupLayout,
+    material_uniform_buffer: Buffer,
+    material_uniform_bind_group: BindGroup,
+    padded_material_uniform_size: u64,
+    /// Compiled materials and their pipelines.
+    materials: ShaderCache<MaterialShader>,
+    post_bind_group_layout: BindGroupLayout,
+    /// Post effect pipelines, `None` if the shader failed to compile.
+    post_pipelines: ShaderCache<Option<RenderPipeline>>,
+    /// Frame-sized textures that post effects take turns reading from and writing to.
+    post_targets: Vec<Texture>,
+    post_uniform_buffer: Buffer,
+    padded_post_uniform_size: u64,
+    /// Checks shader files for changes before every frame when enabled.
+    shader_hot_reload: bool,
+    /// Shader files in use, with their modification time when they were last loaded.
+    watched_shaders: HashMap<String, Option<SystemTime>>,
+    stats: RenderStats,
 }
 
 impl RenderState {
@@ -51,15 +274,13 @@ impl RenderState {
         let size = window.inner_size();
         let instance = Instance::new(&wgpu::InstanceDescriptor::default());
 
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).block_on().unwrap();
+        let surface = instance.create_surface(window).unwrap();
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }).block_on().unwrap();
         let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default()).block_on().unwrap();
 
-        // Calculate alignment
-        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
-        let uniform_size = std::mem::size_of::<Uniform2D>() as u64;
-        let padded_uniform_size = (uniform_size + alignment - 1) & !(alignment - 1);
-
-        let surface = instance.create_surface(window).unwrap();
         let surface_caps = surface.get_capabilities(&adapter);
         let format = surface_caps.formats.iter().find(|f| f.is_srgb()).copied().unwrap_or(surface_caps.formats[0]);
 
@@ -75,167 +296,956 @@ impl RenderState {
         };
         surface.configure(&device, &config);
 
-        let uniform_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: true,
-                    min_binding_size: wgpu::BufferSize::new(uniform_size),
-                },
-                count: None,
-            }],
-            label: Some("uniform_layout"),
-        });
+        Self::with_target(FrameTarget::Surface { surface, config }, adapter, device, queue, format, size)
+    }
 
-        let texture_layout = Self::create_texture_bind_group_layout(&device);
+    /// Creates a render state without a window that draws into an offscreen texture of the given size.
+    /// Any adapter will do, including software ones such as lavapipe or llvmpipe.
+    pub fn new_headless(width: u32, height: u32) -> Result<Self, String> {
+        let size = PhysicalSize::new(max(width, 1), max(height, 1));
+        let instance = Instance::new(&wgpu::InstanceDescriptor::default());
 
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Multi-Instance Uniform Buffer"),
-            size: padded_uniform_size * MAX_INSTANCES as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).block_on()
+            .map_err(|e| format!("No suitable adapter found: {}", e))?;
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default()).block_on()
+            .map_err(|e| format!("Unable to create device: {}", e))?;
 
-        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &uniform_buffer,
-                    offset: 0,
-                    size: wgpu::BufferSize::new(uniform_size),
-                }),
-            }],
-            label: Some("uniform_bind_group"),
-        });
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let texture = Self::create_offscreen_texture(&device, format, size);
+
+        Ok(Self::with_target(FrameTarget::Offscreen { texture, format }, adapter, device, queue, format, size))
+    }
+
+    fn with_target(target: FrameTarget, adapter: Adapter, device: Device, queue: Queue, format: TextureFormat, size: PhysicalSize<u32>) -> Self {
+
+        // Calculate alignment
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let uniform_size = std::mem::size_of::<Uniform2D>() as u64;
+        let padded_uniform_size = (uniform_size + alignment - 1) & !(alignment - 1);
+
+        let uniform_layout = Self::create_uniform_bind_group_layout(&device, "uniform_layout", uniform_size);
+        let uniform_3d_size = std::mem::size_of::<Uniform3D>() as u64;
+        let padded_uniform_3d_size = (uniform_3d_size + alignment - 1) & !(alignment - 1);
+        let uniform_3d_layout = Self::create_uniform_bind_group_layout(&device, "uniform_3d_layout", uniform_3d_size);
+        let material_uniform_size = std::mem::size_of::<MaterialUniform>() as u64;
+        let padded_material_uniform_size = (material_uniform_size + alignment - 1) & !(alignment - 1);
+        let material_uniform_layout = Self::create_uniform_bind_group_layout(&device, "material_uniform_layout", material_uniform_size);
+        let material_texture_layout = Self::create_material_texture_bind_group_layout(&device);
+
+        let texture_layout = Self::create_texture_bind_group_layout(&device);
+        let post_layout = Self::create_post_bind_group_layout(&device);
+        let post_uniform_size = std::mem::size_of::<PostUniform>() as u64;
+        let padded_post_uniform_size = (post_uniform_size + alignment - 1) & !(alignment - 1);
+        let post_uniform_buffer = Self::create_post_uniform_buffer(&device, padded_post_uniform_size, INITIAL_POST_UNIFORM_CAPACITY);
+
+        let (uniform_buffer, uniform_bind_group) = Self::create_uniform_buffer(&device, &uniform_layout, padded_uniform_size, uniform_size, INITIAL_UNIFORM_CAPACITY);
+        let (uniform_3d_buffer, uniform_3d_bind_group) = Self::create_uniform_buffer(&device, &uniform_3d_layout, padded_uniform_3d_size, uniform_3d_size, INITIAL_UNIFORM_CAPACITY);
+        let (material_uniform_buffer, material_uniform_bind_group) = Self::create_uniform_buffer(&device, &material_uniform_layout, padded_material_uniform_size, material_uniform_size, INITIAL_UNIFORM_CAPACITY);
 
         let white_pixel = Image::single_pixel(Color::WHITE);
-        let white_tex = Texture::from_image(&device, white_pixel.image.clone());
-        let (width, height) = white_tex.image.dimensions();
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo { texture: &white_tex.texture, mip_level: 0, origin: wgpu::Origin3d::ZERO, aspect: Default::default() },
-            &white_tex.image,
-            wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(4 * width), rows_per_image: Some(height) },
-            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
-        );
+        let default_white_texture = Self::upload_texture(&device, &queue, &texture_layout, &white_pixel.image.to_rgba8());
 
-        let white_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_layout,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&white_tex.view) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&white_tex.sampler) },
-            ],
-            label: None,
-        });
+        let shader2d = device.create_shader_module(wgpu::include_wgsl!("../shaders/shader2d.wgsl"));
+        let shader2d_instanced = device.create_shader_module(wgpu::include_wgsl!("../shaders/shader2d_instanced.wgsl"));
+        let shader3d = device.create_shader_module(wgpu::include_wgsl!("../shaders/shader3d.wgsl"));
 
-        let shader = device.create_shader_module(wgpu::include_wgsl!("../shaders/shader2d.wgsl"));
-        let render_pipeline = PipelineBuilder::new()
-            .with_vertex_shader(&shader)
-            .with_fragment_shader(&shader)
-            .with_vertex_buffer_layout(Vertex2D::desc())
-            .build(&device, config.format, &[&uniform_layout, &texture_layout])
-            .expect("Failed to create pipeline");
+        let depth_stencil_view = Self::create_depth_stencil_view(&device, size);
+
+        let vertex_arena = BufferArena::new(&device, "Vertex Arena", wgpu::BufferUsages::VERTEX, INITIAL_VERTEX_ARENA_SIZE);
+        let index_arena = BufferArena::new(&device, "Index Arena", wgpu::BufferUsages::INDEX, INITIAL_INDEX_ARENA_SIZE);
+        let instance_arena = BufferArena::new(&device, "Instance Arena", wgpu::BufferUsages::VERTEX, INITIAL_VERTEX_ARENA_SIZE);
 
         Self {
-            surface, adapter, device, queue, config, size,
-            render_pipeline,
+            target, adapter, device, queue, size,
+            depth_stencil_view,
+            format,
+            shader2d,
+            shader2d_instanced,
+            shader3d,
+            pipelines: HashMap::new(),
             uniform2d: Uniform2D::new(),
             uniform_buffer,
             uniform_bind_group,
+            uniform_bind_group_layout: uniform_layout,
+            vertex_arena,
+            index_arena,
+            instance_arena,
             texture_bind_group_layout: texture_layout,
             texture_cache: HashMap::new(),
-            default_white_texture: (white_tex, white_bg),
+            render_targets: HashMap::new(),
+            glyph_atlas: GlyphAtlas::new(),
+            glyph_atlas_texture: None,
+            default_white_texture,
             padded_uniform_size,
+            uniform_3d_layout,
+            uniform_3d_buffer,
+            uniform_3d_bind_group,
+            padded_uniform_3d_size,
+            material_uniform_layout,
+            material_texture_layout,
+            material_uniform_buffer,
+            material_uniform_bind_group,
+            padded_material_uniform_size,
+            materials: HashMap::new(),
+            post_bind_group_layout: post_layout,
+            post_pipelines: HashMap::new(),
+            post_targets: Vec::new(),
+            post_uniform_buffer,
+            padded_post_uniform_size,
+            shader_hot_reload: false,
+            watched_shaders: HashMap::new(),
+            stats: RenderStats {
+                uniform_capacity: INITIAL_UNIFORM_CAPACITY,
+                vertex_arena_capacity: INITIAL_VERTEX_ARENA_SIZE,
+                index_arena_capacity: INITIAL_INDEX_ARENA_SIZE,
+                ..Default::default()
+            },
         }
     }
 
+    /// Returns statistics about the last rendered frame.
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Enables or disables reloading material and post effect shaders loaded from files when the files change.
+    /// Meant for development, as it checks every file before each frame.
+    pub fn set_shader_hot_reload(&mut self, enabled: bool) {
+        self.shader_hot_reload = enabled;
+    }
+
+    /// Returns information about the adapter the renderer is running on.
+    pub fn adapter_info(&self) -> AdapterInfo {
+        self.adapter.get_info()
+    }
+
     pub fn render(&mut self, canvas: &Canvas) {
-        let output = self.surface.get_current_texture().expect("Failed to get surface texture");
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        match &self.target {
+            FrameTarget::Surface { surface, .. } => {
+                let output = surface.get_current_texture().expect("Failed to get surface texture");
+                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                self.draw_frame(canvas, &view);
+                output.present();
+            }
+            FrameTarget::Offscreen { texture, .. } => {
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                self.draw_frame(canvas, &view);
+            }
+        }
+    }
+
+    /// Copies the last rendered frame of an offscreen render state back to the CPU.
+    pub fn read_frame(&self) -> Result<RgbaImage, String> {
+        let FrameTarget::Offscreen { texture, .. } = &self.target else {
+            return Err("Only offscreen render states can be read back".to_string());
+        };
+
+        let (width, height) = (texture.width(), texture.height());
+        let unpadded_bytes_per_row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Readback Encoder") });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo { texture, mip_level: 0, origin: wgpu::Origin3d::ZERO, aspect: Default::default() },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(padded_bytes_per_row), rows_per_image: Some(height) },
+            },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).map_err(|e| format!("Failed to wait for device: {}", e))?;
+        receiver.recv()
+            .map_err(|e| format!("Readback was never completed: {}", e))?
+            .map_err(|e| format!("Failed to map readback buffer: {}", e))?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        RgbaImage::from_raw(width, height, pixels).ok_or_else(|| "Readback buffer has the wrong size".to_string())
+    }
+
+    fn draw_frame(&mut self, canvas: &Canvas, view: &TextureView) {
+        if self.shader_hot_reload {
+            self.reload_changed_shaders();
+        }
+
+        self.release_dropped_render_targets();
+        self.stats.texture_bytes_uploaded = 0;
         let draw_commands = canvas.to_frame();
+        let mut passes = Vec::new();
+        FramePass::collect(draw_commands, canvas.bg_color, None, &mut passes);
+
+        // Effects whose shader fails to compile are left out rather than failing the frame
+        let post_effects: Vec<&PostEffect> = canvas.post_effects().iter()
+            .filter(|effect| self.ensure_post_pipeline(effect))
+            .collect();
+        for effect in &post_effects {
+            if let Some(img) = &effect.image {
+                self.cache_texture(img);
+            }
+        }
+        if !post_effects.is_empty() {
+            self.prepare_post_effects(&post_effects);
+        }
+
+        self.glyph_atlas.begin_frame();
+        for pass in &passes {
+            if let Some(target) = pass.target {
+                self.ensure_render_target(target);
+            }
+            for command in pass.commands {
+                match command {
+                    DrawCommand::DrawMesh2D { .. } | DrawCommand::DrawInstanced2D { .. } | DrawCommand::DrawMesh3D { .. } => {
+                        for img in command.images() {
+                            self.cache_texture(img);
+                        }
+                    }
+                    DrawCommand::DrawText { glyphs, font, .. } => {
+                        for glyph in glyphs {
+                            self.glyph_atlas.prepare(font, glyph.key);
+                        }
+                    }
+                    DrawCommand::SetClipRect(_) | DrawCommand::PushMask { .. } | DrawCommand::PopMask | DrawCommand::RenderToTarget { .. } => {}
+                }
+            }
+        }
+        if self.glyph_atlas.was_cleared() {
+            // Clearing the full atlas dropped the glyphs prepared before it, so prepare the frame's glyphs again
+            for command in passes.iter().flat_map(|pass| pass.commands) {
+                if let DrawCommand::DrawText { glyphs, font, .. } = command {
+                    for glyph in glyphs {
+                        self.glyph_atlas.prepare(font, glyph.key);
+                    }
+                }
+            }
+        }
+        self.sync_glyph_atlas();
+
+        let mut pass_batches: Vec<_> = passes.iter().map(|pass| build_batches(pass.commands, &self.glyph_atlas)).collect();
+
+        // Materials whose shader fails to compile fall back to the built-in shader
+        for batch in pass_batches.iter_mut().flatten() {
+            if let Some(material) = &batch.material && !self.ensure_material_pipeline(material, batch.blend_mode) {
+                batch.material = None;
+            }
+        }
+
+        let batches: Vec<&DrawBatch> = pass_batches.iter().flatten().collect();
+        self.ensure_uniform_capacity(batches.len());
+        for batch in &batches {
+            self.ensure_pipeline(PipelineKey::for_batch(batch));
+        }
+
+        // Stage the uniforms of every batch and upload them in one go
+        let stride = self.padded_uniform_size as usize;
+        let mut uniform_data = vec![0u8; batches.len() * stride];
+        for (i, batch) in batches.iter().enumerate() {
+            self.uniform2d.update_camera(batch.camera_matrix);
+            self.uniform2d.update_transform(batch.transform);
+            self.uniform2d.set_use_texture(batch.texture.is_some());
+            uniform_data[i * stride..i * stride + size_of::<Uniform2D>()].copy_from_slice(bytemuck::bytes_of(&self.uniform2d));
+        }
+        if !uniform_data.is_empty() {
+            self.queue.write_buffer(&self.uniform_buffer, 0, &uniform_data);
+        }
+
+        // 3D draws keep their uniforms in a buffer of their own, at the batch's index
+        let stride_3d = self.padded_uniform_3d_size as usize;
+        let count_3d = batches.iter().rposition(|batch| batch.is_3d()).map_or(0, |i| i + 1);
+        let mut uniform_3d_data = vec![0u8; count_3d * stride_3d];
+        for (i, batch) in batches.iter().enumerate() {
+            if let Some(material) = &batch.material_3d {
+                let uniform = Uniform3D::new(batch.camera_matrix, batch.transform, material, batch.texture.is_some(), batch.lighting.as_ref());
+                uniform_3d_data[i * stride_3d..i * stride_3d + size_of::<Uniform3D>()].copy_from_slice(bytemuck::bytes_of(&uniform));
+            }
+        }
+        if !uniform_3d_data.is_empty() {
+            self.queue.write_buffer(&self.uniform_3d_buffer, 0, &uniform_3d_data);
+        }
+
+        // Material parameters share the batch's index, so only the range up to the last material batch is uploaded
+        let material_stride = self.padded_material_uniform_size as usize;
+        let material_count = batches.iter().rposition(|batch| batch.material.is_some()).map_or(0, |i| i + 1);
+        let mut material_data = vec![0u8; material_count * material_stride];
+        let mut material_texture_groups = vec![None; batches.len()];
+        for (i, batch) in batches.iter().enumerate() {
+            if let Some(material) = &batch.material {
+                let uniform = MaterialUniform::new(material.params);
+                material_data[i * material_stride..i * material_stride + size_of::<MaterialUniform>()].copy_from_slice(bytemuck::bytes_of(&uniform));
+                material_texture_groups[i] = Some(self.create_material_texture_bind_group(material));
+            }
+        }
+        if !material_data.is_empty() {
+            self.queue.write_buffer(&self.material_uniform_buffer, 0, &material_data);
+        }
+
+        // Sub-allocate the geometry of every batch from the persistent arenas
+        self.vertex_arena.clear();
+        self.index_arena.clear();
+        self.instance_arena.clear();
+        let ranges: Vec<_> = batches.iter()
+            .map(|batch| (
+                if batch.is_3d() { self.vertex_arena.push(&batch.vertices_3d) } else { self.vertex_arena.push(&batch.vertices) },
+                self.index_arena.push(&batch.indices),
+                self.instance_arena.push(&batch.instances),
+            ))
+            .collect();
+        let bytes_uploaded = uniform_data.len() as u64
+            + uniform_3d_data.len() as u64
+            + material_data.len() as u64
+            + self.vertex_arena.upload(&self.device, &self.queue)
+            + self.index_arena.upload(&self.device, &self.queue)
+            + self.instance_arena.upload(&self.device, &self.queue);
+
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let mut draw_calls = 0;
+        let mut triangles = 0;
+        let mut first_batch = 0;
+
+        for (pass, pass_batches) in passes.iter().zip(&pass_batches) {
+            let batch_range = first_batch..first_batch + pass_batches.len();
+            first_batch = batch_range.end;
+
+            let (color_view, depth_stencil_view, size) = match pass.target {
+                Some(target) => {
+                    let key = target.key();
+                    (&self.texture_cache[&key].0.view, &self.render_targets[&key].depth_stencil, PhysicalSize::new(target.width(), target.height()))
+                }
+                // With post effects the frame is drawn into the first post target instead
+                None if post_effects.is_empty() => (view, &self.depth_stencil_view, self.size),
+                None => (&self.post_targets[0].view, &self.depth_stencil_view, self.size),
+            };
 
-        {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: color_view,
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(canvas.bg_color.into()),
+                        load: wgpu::LoadOp::Clear(pass.bg_color.into()),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth_stencil_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                }),
                 ..Default::default()
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
+            let mut current_pipeline = None;
+            let mut current_clip = None;
+            let mut current_stencil_reference = 0;
+            let (target_width, target_height) = (max(size.width, 1), max(size.height, 1));
 
-            for (i, command) in draw_commands.iter().enumerate() {
-                if i >= MAX_INSTANCES { break; }
+            for i in batch_range {
+                let batch = batches[i];
+                let (vertex_range, index_range, instance_range) = ranges[i].clone();
+                if batch.indices.is_empty() || batch.instance_count() == 0 {
+                    continue;
+                }
 
-                match command {
-                    DrawCommand::DrawMesh2D { mesh, camera_matrix, transform, style } => {
-                        self.uniform2d.update_camera(*camera_matrix);
-                        self.uniform2d.update_transform(transform);
-                        self.uniform2d.set_use_texture(style.image.is_some());
-
-                        // Use the padded offset
-                        let offset = i as u64 * self.padded_uniform_size;
-                        self.queue.write_buffer(&self.uniform_buffer, offset, bytemuck::cast_slice(&[self.uniform2d]));
-
-                        render_pass.set_bind_group(0, &self.uniform_bind_group, &[offset as u32]);
-
-                        let bind_group_1 = if let Some(img) = &style.image {
-                            let device = &self.device;
-                            let queue = &self.queue;
-                            let layout = &self.texture_bind_group_layout;
-                            let entry = self.texture_cache.entry(img.path.clone()).or_insert_with(|| {
-                                let tex = Texture::from_image(device, img.image.clone());
-                                let (w, h) = tex.image.dimensions();
-                                queue.write_texture(
-                                    wgpu::TexelCopyTextureInfo { texture: &tex.texture, mip_level: 0, origin: wgpu::Origin3d::ZERO, aspect: Default::default() },
-                                    &tex.image,
-                                    wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(4 * w), rows_per_image: Some(h) },
-                                    wgpu::Extent3d { width: w, height: h, depth_or_array_layers: 1 },
-                                );
-                                let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                                    layout,
-                                    entries: &[
-                                        wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&tex.view) },
-                                        wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&tex.sampler) },
-                                    ],
-                                    label: None,
-                                });
-                                (tex, bg)
-                            });
-                            &entry.1
-                        } else {
-                            &self.default_white_texture.1
-                        };
-
-                        render_pass.set_bind_group(1, bind_group_1, &[]);
-
-                        let v_buf = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: None, contents: bytemuck::cast_slice(&mesh.vertices), usage: wgpu::BufferUsages::VERTEX,
-                        });
-                        let i_buf = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: None, contents: bytemuck::cast_slice(&mesh.indices), usage: wgpu::BufferUsages::INDEX,
-                        });
-
-                        render_pass.set_vertex_buffer(0, v_buf.slice(..));
-                        render_pass.set_index_buffer(i_buf.slice(..), wgpu::IndexFormat::Uint16);
-                        render_pass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
-                    }
+                // Scissor to the batch's clip rectangle, skipping batches clipped away entirely
+                let scissor = match batch.clip {
+                    Some(clip) => match clip.to_pixels(target_width, target_height) {
+                        Some(scissor) => scissor,
+                        None => continue,
+                    },
+                    None => [0, 0, target_width, target_height],
+                };
+                if current_clip != Some(scissor) {
+                    current_clip = Some(scissor);
+                    let [x, y, width, height] = scissor;
+                    render_pass.set_scissor_rect(x, y, width, height);
+                }
+
+                if batch.stencil_reference != current_stencil_reference {
+                    current_stencil_reference = batch.stencil_reference;
+                    render_pass.set_stencil_reference(current_stencil_reference);
+                }
+
+                // Only switch pipelines when the blend mode, instancing, stencil mode or material shader changes
+                let key = (PipelineKey::for_batch(batch), batch.material.as_ref().map(|material| material.shader_key()));
+                if current_pipeline != Some(key) {
+                    current_pipeline = Some(key);
+                    let pipeline = match key.1 {
+                        Some((path, source)) => self.materials[path][source].pipelines[&batch.blend_mode].as_ref().expect("Material pipeline was not compiled"),
+                        None => &self.pipelines[&key.0],
+                    };
+                    render_pass.set_pipeline(pipeline);
                 }
+
+                // Use the padded offset
+                if batch.is_3d() {
+                    let offset = i as u64 * self.padded_uniform_3d_size;
+                    render_pass.set_bind_group(0, &self.uniform_3d_bind_group, &[offset as u32]);
+                } else {
+                    let offset = i as u64 * self.padded_uniform_size;
+                    render_pass.set_bind_group(0, &self.uniform_bind_group, &[offset as u32]);
+                }
+
+                if let Some(material_textures) = &material_texture_groups[i] {
+                    let offset = i as u64 * self.padded_material_uniform_size;
+                    render_pass.set_bind_group(2, &self.material_uniform_bind_group, &[offset as u32]);
+                    render_pass.set_bind_group(3, material_textures, &[]);
+                }
+
+                // Images that could not be uploaded, such as render targets never drawn into, fall back to white
+                let texture = match &batch.texture {
+                    Some(BatchTexture::Image(path)) => self.texture_cache.get(path),
+                    Some(BatchTexture::GlyphAtlas) => self.glyph_atlas_texture.as_ref(),
+                    None => None,
+                };
+                let bind_group_1 = texture.map_or(&self.default_white_texture.1, |(_, bind_group)| bind_group);
+
+                render_pass.set_bind_group(1, bind_group_1, &[]);
+
+                render_pass.set_vertex_buffer(0, self.vertex_arena.buffer().slice(vertex_range));
+                render_pass.set_index_buffer(self.index_arena.buffer().slice(index_range), wgpu::IndexFormat::Uint16);
+                if batch.is_instanced() {
+                    render_pass.set_vertex_buffer(1, self.instance_arena.buffer().slice(instance_range));
+                }
+                render_pass.draw_indexed(0..batch.indices.len() as u32, 0, 0..batch.instance_count());
+                draw_calls += 1;
+                triangles += batch.indices.len() / 3 * batch.instance_count() as usize;
             }
         }
 
+        draw_calls += self.encode_post_effects(&mut encoder, &post_effects, view);
+
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+
+        self.stats.draw_commands = draw_commands.len();
+        self.stats.draw_calls = draw_calls;
+        self.stats.triangles = triangles;
+        self.stats.bytes_uploaded = bytes_uploaded;
+        self.stats.vertex_arena_capacity = self.vertex_arena.capacity();
+        self.stats.index_arena_capacity = self.index_arena.capacity();
+        self.stats.arena_reallocations = self.vertex_arena.reallocations() + self.index_arena.reallocations() + self.instance_arena.reallocations();
+        self.stats.render_targets = self.render_targets.len();
+    }
+
+    /// Creates the pipeline of the post effect unless it is already cached, returning false if its shader is invalid.
+    fn ensure_post_pipeline(&mut self, effect: &PostEffect) -> bool {
+        let (path, source) = effect.shader_key();
+        if let Some(pipeline) = self.post_pipelines.get(path).and_then(|sources| sources.get(source)) {
+            return pipeline.is_some();
+        }
+
+        let pipeline = self.create_post_pipeline(&effect.shader)
+            .inspect_err(|e| warn!("Failed to create post effect pipeline: {}", e))
+            .ok();
+        if let Some(path) = &effect.shader_path {
+            self.watch_shader(path);
+        }
+
+        let compiled = pipeline.is_some();
+        self.post_pipelines.entry(path.to_string()).or_default().insert(source.to_string(), pipeline);
+        compiled
+    }
+
+    fn create_post_pipeline(&self, shader: &str) -> Result<RenderPipeline, String> {
+        Self::validate(&self.device, || {
+            let module = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Post Effect Shader"),
+                source: wgpu::ShaderSource::Wgsl(format!("{}\n{}", POST_PRELUDE, shader).into()),
+            });
+            PipelineBuilder::new()
+                .with_label("Post Effect Pipeline")
+                .with_vertex_shader(&module)
+                .with_fragment_shader(&module)
+                .with_cull_mode(None)
+                .build(&self.device, self.format, &[&self.post_bind_group_layout])
+        })?
+    }
+
+    /// Makes sure the post targets match the frame size and uploads the uniforms of every effect.
+    fn prepare_post_effects(&mut self, effects: &[&PostEffect]) {
+        let (width, height) = (max(self.size.width, 1), max(self.size.height, 1));
+        if self.post_targets.first().is_none_or(|tex| (tex.texture.width(), tex.texture.height()) != (width, height)) {
+            self.post_targets = (0..2).map(|_| Texture::render_target(&self.device, width, height, self.format)).collect();
+        }
+
+        let stride = self.padded_post_uniform_size as usize;
+        if (effects.len() * stride) as u64 > self.post_uniform_buffer.size() {
+            self.post_uniform_buffer = Self::create_post_uniform_buffer(&self.device, self.padded_post_uniform_size, effects.len().next_power_of_two());
+        }
+
+        let mut uniform_data = vec![0u8; effects.len() * stride];
+        for (i, effect) in effects.iter().enumerate() {
+            let uniform = PostUniform::new(width, height, effect.params);
+            uniform_data[i * stride..i * stride + size_of::<PostUniform>()].copy_from_slice(bytemuck::bytes_of(&uniform));
+        }
+        self.queue.write_buffer(&self.post_uniform_buffer, 0, &uniform_data);
+    }
+
+    /// Applies the post effects in order, each reading the previous result, with the last one writing to the view.
+    /// Returns the number of draw calls issued.
+    fn encode_post_effects(&self, encoder: &mut wgpu::CommandEncoder, effects: &[&PostEffect], view: &TextureView) -> usize {
+        for (i, effect) in effects.iter().enumerate() {
+            let source = &self.post_targets[i % 2];
+            let destination = if i + 1 == effects.len() { view } else { &self.post_targets[(i + 1) % 2].view };
+            let extra = effect.image.as_ref()
+                .and_then(|img| self.texture_cache.get(&img.path))
+                .map_or(&self.default_white_texture.0, |(tex, _)| tex);
+
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.post_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &self.post_uniform_buffer,
+                            offset: i as u64 * self.padded_post_uniform_size,
+                            size: wgpu::BufferSize::new(size_of::<PostUniform>() as u64),
+                        }),
+                    },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&source.view) },
+                    wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&source.sampler) },
+                    wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&extra.view) },
+                    wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&extra.sampler) },
+                ],
+                label: Some("post_bind_group"),
+            });
+
+            let (shader_path, shader_source) = effect.shader_key();
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Effect Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: destination,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            render_pass.set_pipeline(self.post_pipelines[shader_path][shader_source].as_ref().expect("Post effect pipeline was not compiled"));
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        effects.len()
+    }
+
+    /// Creates the pipeline for the key unless it is already cached.
+    fn ensure_pipeline(&mut self, key: PipelineKey) {
+        if self.pipelines.contains_key(&key) {
+            return;
+        }
+
+        if key.mesh_3d {
+            self.ensure_pipeline_3d(key);
+            return;
+        }
+
+        let builder = if key.instanced {
+            PipelineBuilder::new()
+                .with_label("2D Instanced Pipeline")
+                .with_vertex_shader(&self.shader2d_instanced)
+                .with_fragment_shader(&self.shader2d_instanced)
+                .with_vertex_buffer_layout(Vertex2D::desc())
+                .with_vertex_buffer_layout(InstanceData::desc())
+        } else {
+            PipelineBuilder::new()
+                .with_label("2D Pipeline")
+                .with_vertex_shader(&self.shader2d)
+                .with_fragment_shader(&self.shader2d)
+                .with_vertex_buffer_layout(Vertex2D::desc())
+        };
+
+        // 2D geometry may be mirrored by the camera or transform, so both windings are drawn
+        let (depth_stencil, color_writes) = Self::stencil_state(key.stencil);
+        let pipeline = builder
+            .with_blend_state(key.blend_mode.into())
+            .with_cull_mode(None)
+            .with_color_writes(color_writes)
+            .with_depth_stencil(depth_stencil)
+            .build(&self.device, self.format, &[&self.uniform_bind_group_layout, &self.texture_bind_group_layout])
+            .expect("Failed to create pipeline");
+
+        self.pipelines.insert(key, pipeline);
+    }
+
+    /// Creates a 3D pipeline, which culls back faces and keeps the surface closest to the camera.
+    fn ensure_pipeline_3d(&mut self, key: PipelineKey) {
+        let (mut depth_stencil, color_writes) = Self::stencil_state(key.stencil);
+        depth_stencil.depth_write_enabled = true;
+        depth_stencil.depth_compare = wgpu::CompareFunction::Less;
+
+        let pipeline = PipelineBuilder::new()
+            .with_label("3D Pipeline")
+            .with_vertex_shader(&self.shader3d)
+            .with_fragment_shader(&self.shader3d)
+            .with_vertex_buffer_layout(Vertex3D::desc())
+            .with_blend_state(key.blend_mode.into())
+            .with_front_face(wgpu::FrontFace::Ccw)
+            .with_cull_mode(Some(wgpu::Face::Back))
+            .with_color_writes(color_writes)
+            .with_depth_stencil(depth_stencil)
+            .build(&self.device, self.format, &[&self.uniform_3d_layout, &self.texture_bind_group_layout])
+            .expect("Failed to create 3D pipeline");
+
+        self.pipelines.insert(key, pipeline);
+    }
+
+    /// Returns the depth-stencil state and color writes of 2D pipelines using the stencil mode.
+    fn stencil_state(stencil: StencilMode) -> (wgpu::DepthStencilState, wgpu::ColorWrites) {
+        // Masks only write to the stencil buffer
+        let (pass_op, color_writes) = match stencil {
+            StencilMode::Test => (wgpu::StencilOperation::Keep, wgpu::ColorWrites::ALL),
+            StencilMode::Increment => (wgpu::StencilOperation::IncrementClamp, wgpu::ColorWrites::empty()),
+            StencilMode::Decrement => (wgpu::StencilOperation::DecrementClamp, wgpu::ColorWrites::empty()),
+        };
+        let face = wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::Equal,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op,
+        };
+        let depth_stencil = wgpu::DepthStencilState {
+            format: DEPTH_STENCIL_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState {
+                front: face,
+                back: face,
+                read_mask: 0xff,
+                write_mask: 0xff,
+            },
+            bias: wgpu::DepthBiasState::default(),
+        };
+        (depth_stencil, color_writes)
+    }
+
+    /// Compiles the material's shader and creates its pipeline for the blend mode unless they are already cached,
+    /// returning false if either failed.
+    fn ensure_material_pipeline(&mut self, material: &Material, blend_mode: BlendMode) -> bool {
+        let (path, source) = material.shader_key();
+        if !self.materials.get(path).is_some_and(|sources| sources.contains_key(source)) {
+            let module = self.compile_material_shader(&material.shader)
+                .inspect_err(|e| warn!("Failed to compile material shader: {}", e))
+                .ok();
+            if let Some(path) = &material.shader_path {
+                self.watch_shader(path);
+            }
+            self.materials.entry(path.to_string()).or_default().insert(source.to_string(), MaterialShader { module, pipelines: HashMap::new() });
+        }
+
+        let compiled = &self.materials[path][source];
+        let Some(module) = &compiled.module else {
+            return false;
+        };
+        if let Some(pipeline) = compiled.pipelines.get(&blend_mode) {
+            return pipeline.is_some();
+        }
+
+        let pipeline = self.create_material_pipeline(module, blend_mode)
+            .inspect_err(|e| warn!("Failed to create material pipeline: {}", e))
+            .ok();
+        let created = pipeline.is_some();
+        self.materials.get_mut(path).and_then(|sources| sources.get_mut(source)).expect("Material was just compiled").pipelines.insert(blend_mode, pipeline);
+        created
+    }
+
+    fn compile_material_shader(&self, shader: &str) -> Result<ShaderModule, String> {
+        // Materials without a vertex stage of their own get the built-in one
+        let vertex = if shader.contains("fn vs_main") { "" } else { "@vertex\nfn vs_main(in: VertexInput) -> VertexOutput {\n    return default_vertex(in);\n}\n" };
+        Self::validate(&self.device, || self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Material Shader"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}\n{}\n{}", MATERIAL_PRELUDE, vertex, shader).into()),
+        }))
+    }
+
+    fn create_material_pipeline(&self, module: &ShaderModule, blend_mode: BlendMode) -> Result<RenderPipeline, String> {
+        let (depth_stencil, color_writes) = Self::stencil_state(StencilMode::Test);
+        Self::validate(&self.device, || {
+            PipelineBuilder::new()
+                .with_label("Material Pipeline")
+                .with_vertex_shader(module)
+                .with_fragment_shader(module)
+                .with_vertex_buffer_layout(Vertex2D::desc())
+                .with_blend_state(blend_mode.into())
+                .with_cull_mode(None)
+                .with_color_writes(color_writes)
+                .with_depth_stencil(depth_stencil)
+                .build(&self.device, self.format, &[
+                    &self.uniform_bind_group_layout,
+                    &self.texture_bind_group_layout,
+                    &self.material_uniform_layout,
+                    &self.material_texture_layout,
+                ])
+        })?
+    }
+
+    /// Runs the closure, returning the first validation error it caused instead of letting wgpu panic.
+    fn validate<T>(device: &Device, create: impl FnOnce() -> T) -> Result<T, String> {
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let value = create();
+        match scope.pop().block_on() {
+            None => Ok(value),
+            Some(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Starts tracking the modification time of the shader file, so it can be reloaded when hot reload is enabled.
+    fn watch_shader(&mut self, path: &str) {
+        if !self.watched_shaders.contains_key(path) {
+            self.watched_shaders.insert(path.to_string(), Self::modified_time(path));
+        }
+    }
+
+    fn modified_time(path: &str) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Recompiles the materials and post effects whose shader files changed since they were last loaded.
+    /// A shader that fails to compile is reported and the previous version stays in use.
+    fn reload_changed_shaders(&mut self) {
+        let changed: Vec<String> = self.watched_shaders.iter_mut()
+            .filter_map(|(path, modified)| {
+                let current = Self::modified_time(path);
+                (current != *modified).then(|| {
+                    *modified = current;
+                    path.clone()
+                })
+            })
+            .collect();
+
+        for path in changed {
+            let source = match read_shader(&path) {
+                Ok(source) => source,
+                Err(e) => {
+                    warn!("{}", e);
+                    continue;
+                }
+            };
+
+            let loaded: Vec<String> = self.materials.get(&path).into_iter().flat_map(|sources| sources.keys().cloned()).collect();
+            for previous in loaded {
+                match self.recompile_material(&self.materials[&path][&previous], &source) {
+                    Ok(compiled) => {
+                        self.materials.get_mut(&path).expect("Material was loaded").insert(previous, compiled);
+                        info!("Reloaded material shader {}", path);
+                    }
+                    Err(e) => warn!("Failed to reload material shader {}, keeping the previous version: {}", path, e),
+                }
+            }
+            let loaded: Vec<String> = self.post_pipelines.get(&path).into_iter().flat_map(|sources| sources.keys().cloned()).collect();
+            for previous in loaded {
+                match self.create_post_pipeline(&source) {
+                    Ok(pipeline) => {
+                        self.post_pipelines.get_mut(&path).expect("Post effect was loaded").insert(previous, Some(pipeline));
+                        info!("Reloaded post effect shader {}", path);
+                    }
+                    Err(e) => warn!("Failed to reload post effect shader {}, keeping the previous version: {}", path, e),
+                }
+            }
+        }
+    }
+
+    /// Compiles the new source of a material and recreates its pipelines for every blend mode the previous version was drawn with.
+    fn recompile_material(&self, previous: &MaterialShader, source: &str) -> Result<MaterialShader, String> {
+        let module = self.compile_material_shader(source)?;
+        let pipelines = previous.pipelines.keys()
+            .map(|&blend_mode| Ok((blend_mode, Some(self.create_material_pipeline(&module, blend_mode)?))))
+            .collect::<Result<_, String>>()?;
+        Ok(MaterialShader { module: Some(module), pipelines })
+    }
+
+    /// Binds the material's extra textures, with white in the slots it leaves unused.
+    fn create_material_texture_bind_group(&self, material: &Material) -> BindGroup {
+        let textures: Vec<&Texture> = (0..MAX_MATERIAL_TEXTURES)
+            .map(|slot| material.textures.get(slot)
+                .and_then(|img| self.texture_cache.get(&img.path))
+                .map_or(&self.default_white_texture.0, |(tex, _)| tex))
+            .collect();
+        let entries: Vec<_> = textures.iter().enumerate()
+            .flat_map(|(slot, tex)| [
+                wgpu::BindGroupEntry { binding: 2 * slot as u32, resource: wgpu::BindingResource::TextureView(&tex.view) },
+                wgpu::BindGroupEntry { binding: 2 * slot as u32 + 1, resource: wgpu::BindingResource::Sampler(&tex.sampler) },
+            ])
+            .collect();
+
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.material_texture_layout,
+            entries: &entries,
+            label: Some("material_texture_bind_group"),
+        })
+    }
+
+    /// Grows the uniform buffers so they can hold one uniform block per draw command.
+    fn ensure_uniform_capacity(&mut self, count: usize) {
+        if count <= self.stats.uniform_capacity {
+            return;
+        }
+
+        let capacity = count.next_power_of_two();
+        warn!("Growing uniform buffer from {} to {} draws", self.stats.uniform_capacity, capacity);
+
+        let (buffer, bind_group) = Self::create_uniform_buffer(&self.device, &self.uniform_bind_group_layout, self.padded_uniform_size, size_of::<Uniform2D>() as u64, capacity);
+        self.uniform_buffer = buffer;
+        self.uniform_bind_group = bind_group;
+        let (buffer, bind_group) = Self::create_uniform_buffer(&self.device, &self.uniform_3d_layout, self.padded_uniform_3d_size, size_of::<Uniform3D>() as u64, capacity);
+        self.uniform_3d_buffer = buffer;
+        self.uniform_3d_bind_group = bind_group;
+        let (buffer, bind_group) = Self::create_uniform_buffer(&self.device, &self.material_uniform_layout, self.padded_material_uniform_size, size_of::<MaterialUniform>() as u64, capacity);
+        self.material_uniform_buffer = buffer;
+        self.material_uniform_bind_group = bind_group;
+        self.stats.uniform_capacity = capacity;
+        self.stats.uniform_reallocations += 1;
+    }
+
+    fn create_uniform_buffer(device: &Device, layout: &BindGroupLayout, padded_uniform_size: u64, uniform_size: u64, capacity: usize) -> (Buffer, BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Multi-Instance Uniform Buffer"),
+            size: padded_uniform_size * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(uniform_size),
+                }),
+            }],
+            label: Some("uniform_bind_group"),
+        });
+
+        (buffer, bind_group)
+    }
+
+    /// Uploads the glyph atlas if new glyphs were rasterized, recreating the texture when it grew.
+    fn sync_glyph_atlas(&mut self) {
+        let Some((atlas, region)) = self.glyph_atlas.take_changes() else {
+            return;
+        };
+
+        // Only the newly rasterized glyphs need uploading while the texture still matches the atlas size
+        match &self.glyph_atlas_texture {
+            Some((tex, _)) if (tex.texture.width(), tex.texture.height()) == atlas.dimensions() => {
+                Self::write_texture(&self.queue, &tex.texture, atlas, region);
+                self.stats.texture_bytes_uploaded += 4 * region[2] as u64 * region[3] as u64;
+            }
+            _ => {
+                self.glyph_atlas_texture = Some(Self::upload_texture(&self.device, &self.queue, &self.texture_bind_group_layout, atlas));
+                self.stats.texture_bytes_uploaded += atlas.as_raw().len() as u64;
+            }
+        }
+    }
+
+    /// Uploads the image to the GPU unless it is already in the texture cache.
+    ///
+    /// Images without pixels refer to textures that only exist on the GPU, such as render targets, and are never uploaded.
+    fn cache_texture(&mut self, img: &Image) {
+        if self.texture_cache.contains_key(&img.path) {
+            return;
+        }
+        if img.image.width() == 0 || img.image.height() == 0 {
+            warn!("Image {} has no pixels and no texture on the GPU, drawing it untextured", img.path);
+            return;
+        }
+
+        let pixels = img.image.to_rgba8();
+        let entry = Self::upload_texture(&self.device, &self.queue, &self.texture_bind_group_layout, &pixels);
+        self.texture_cache.insert(img.path.clone(), entry);
+        self.stats.texture_bytes_uploaded += pixels.as_raw().len() as u64;
+    }
+
+    /// Creates the render target's color and depth-stencil textures unless they already exist at the right size.
+    fn ensure_render_target(&mut self, target: &RenderTarget) {
+        let key = target.key();
+        if let Some((tex, _)) = self.texture_cache.get(&key) && (tex.texture.width(), tex.texture.height()) == (target.width(), target.height()) {
+            return;
+        }
+
+        let tex = Texture::render_target(&self.device, target.width(), target.height(), self.format);
+        let entry = Self::bind_texture(&self.device, &self.texture_bind_group_layout, tex);
+        self.texture_cache.insert(key.clone(), entry);
+
+        let depth_stencil = Self::create_depth_stencil_view(&self.device, PhysicalSize::new(target.width(), target.height()));
+        self.render_targets.insert(key, RenderTargetTextures { depth_stencil, handle: target.handle() });
+    }
+
+    /// Drops the textures of render targets that no longer exist outside the renderer.
+    fn release_dropped_render_targets(&mut self) {
+        let texture_cache = &mut self.texture_cache;
+        self.render_targets.retain(|key, target| {
+            let alive = target.handle.strong_count() > 0;
+            if !alive {
+                debug!("Releasing textures of dropped render target {}", key);
+                texture_cache.remove(key);
+            }
+            alive
+        });
+    }
+
+    /// Copies the pixels into a new texture and creates a bind group for sampling it.
+    fn upload_texture(device: &Device, queue: &Queue, layout: &BindGroupLayout, image: &RgbaImage) -> (Texture, BindGroup) {
+        let (width, height) = image.dimensions();
+        let tex = Texture::new(device, width, height);
+        Self::write_texture(queue, &tex.texture, image, [0, 0, width, height]);
+        Self::bind_texture(device, layout, tex)
+    }
+
+    /// Creates a bind group for sampling the texture.
+    fn bind_texture(device: &Device, layout: &BindGroupLayout, tex: Texture) -> (Texture, BindGroup) {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&tex.view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&tex.sampler) },
+            ],
+            label: None,
+        });
+        (tex, bg)
+    }
+
+    /// Copies the region of the image, given as x, y, width and height, to the same place in the texture.
+    fn write_texture(queue: &Queue, texture: &wgpu::Texture, image: &RgbaImage, [x, y, w, h]: [u32; 4]) {
+        let offset = 4 * (y as u64 * image.width() as u64 + x as u64);
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo { texture, mip_level: 0, origin: wgpu::Origin3d { x, y, z: 0 }, aspect: Default::default() },
+            image,
+            wgpu::TexelCopyBufferLayout { offset, bytes_per_row: Some(4 * image.width()), rows_per_image: Some(h) },
+            wgpu::Extent3d { width: w, height: h, depth_or_array_layers: 1 },
+        );
     }
 
     fn create_texture_bind_group_layout(device: &Device) -> BindGroupLayout {
@@ -262,10 +1272,132 @@ impl RenderState {
         })
     }
 
+    fn create_post_uniform_buffer(device: &Device, padded_uniform_size: u64, capacity: usize) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Effect Uniform Buffer"),
+            size: padded_uniform_size * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_uniform_bind_group_layout(device: &Device, label: &str, uniform_size: u64) -> BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(uniform_size),
+                },
+                count: None,
+            }],
+            label: Some(label),
+        })
+    }
+
+    fn create_material_texture_bind_group_layout(device: &Device) -> BindGroupLayout {
+        let entries: Vec<_> = (0..MAX_MATERIAL_TEXTURES as u32)
+            .flat_map(|slot| [Self::texture_layout_entry(2 * slot), Self::sampler_layout_entry(2 * slot + 1)])
+            .collect();
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &entries,
+            label: Some("material_texture_bind_group_layout"),
+        })
+    }
+
+    fn texture_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }
+    }
+
+    fn sampler_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        }
+    }
+
+    fn create_post_bind_group_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<PostUniform>() as u64),
+                    },
+                    count: None,
+                },
+                Self::texture_layout_entry(1),
+                Self::sampler_layout_entry(2),
+                Self::texture_layout_entry(3),
+                Self::sampler_layout_entry(4),
+            ],
+            label: Some("post_bind_group_layout"),
+        })
+    }
+
+    fn create_offscreen_texture(device: &Device, format: TextureFormat, size: PhysicalSize<u32>) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    fn create_depth_stencil_view(device: &Device, size: PhysicalSize<u32>) -> TextureView {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Stencil Texture"),
+            size: wgpu::Extent3d {
+                width: max(size.width, 1),
+                height: max(size.height, 1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_STENCIL_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }).create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         self.size = new_size;
-        self.config.width = max(new_size.width, 1);
-        self.config.height = max(new_size.height, 1);
-        self.surface.configure(&self.device, &self.config);
+        self.depth_stencil_view = Self::create_depth_stencil_view(&self.device, new_size);
+        match &mut self.target {
+            FrameTarget::Surface { surface, config } => {
+                config.width = max(new_size.width, 1);
+                config.height = max(new_size.height, 1);
+                surface.configure(&self.device, config);
+            }
+            FrameTarget::Offscreen { texture, format } => {
+                let size = PhysicalSize::new(max(new_size.width, 1), max(new_size.height, 1));
+                *texture = Self::create_offscreen_texture(&self.device, *format, size);
+            }
+        }
     }
 }
\ No newline at end of file
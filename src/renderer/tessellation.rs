@@ -0,0 +1,530 @@
+use crate::graphics::path::FillRule;
+use crate::graphics::stroke::{LineCap, LineJoin, Stroke};
+use crate::renderer::mesh::Mesh;
+use crate::renderer::vertex::Vertex2D;
+use cgmath::{InnerSpace, Vector2};
+use log::warn;
+use std::f32::consts::PI;
+use std::collections::HashMap;
+
+/// Segments used for a full circle when rounding joins and caps.
+const ROUND_SEGMENTS: usize = 32;
+
+/// Collects triangles, winding them all clockwise like the other 2D meshes.
+pub struct TriangleBuilder {
+    positions: Vec<[f32; 2]>,
+    indices: Vec<u16>,
+}
+
+impl TriangleBuilder {
+
+    pub fn new() -> Self {
+        Self {
+            positions: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    pub fn vertex(&mut self, p: Vector2<f32>) -> u16 {
+        self.positions.push([p.x, p.y]);
+        (self.positions.len() - 1) as u16
+    }
+
+    /// Adds a triangle between existing vertices, in clockwise order.
+    pub fn triangle(&mut self, a: u16, b: u16, c: u16) {
+        let [pa, pb, pc] = [a, b, c].map(|i| Vector2::from(self.positions[i as usize]));
+        let area = (pb - pa).perp_dot(pc - pa);
+        if area.abs() <= f32::EPSILON {
+            return;
+        }
+        if area < 0.0 {
+            self.indices.extend_from_slice(&[a, b, c]);
+        } else {
+            self.indices.extend_from_slice(&[a, c, b]);
+        }
+    }
+
+    pub fn quad(&mut self, a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, d: Vector2<f32>) {
+        let [a, b, c, d] = [a, b, c, d].map(|p| self.vertex(p));
+        self.triangle(a, b, c);
+        self.triangle(a, c, d);
+    }
+
+    /// Adds a fan of triangles around the center following an arc from `start` to `end` angle.
+    pub fn fan(&mut self, center: Vector2<f32>, radius: f32, start: f32, end: f32) {
+        let steps = (((end - start).abs() / (2.0 * PI)) * ROUND_SEGMENTS as f32).ceil().max(1.0) as usize;
+        let c = self.vertex(center);
+        let mut previous = self.vertex(center + Vector2::new(start.cos(), start.sin()) * radius);
+        for i in 1..=steps {
+            let angle = start + (end - start) * i as f32 / steps as f32;
+            let next = self.vertex(center + Vector2::new(angle.cos(), angle.sin()) * radius);
+            self.triangle(c, previous, next);
+            previous = next;
+        }
+    }
+
+    /// Builds the mesh, mapping UVs onto the bounding box of the geometry.
+    pub fn build(self, color: [f32; 4]) -> Mesh<Vertex2D> {
+        if self.positions.len() > u16::MAX as usize + 1 {
+            warn!("Tessellated mesh needs {} vertices, more than 16-bit indices allow", self.positions.len());
+            return Mesh { vertices: Vec::new(), indices: Vec::new() };
+        }
+
+        let (min, max) = bounds(&self.positions);
+        let size = [(max[0] - min[0]).max(f32::EPSILON), (max[1] - min[1]).max(f32::EPSILON)];
+
+        let vertices = self.positions.iter()
+            .map(|p| Vertex2D {
+                position: *p,
+                uv: [(p[0] - min[0]) / size[0], (max[1] - p[1]) / size[1]],
+                color,
+            })
+            .collect();
+
+        Mesh {
+            vertices,
+            indices: self.indices,
+        }
+    }
+}
+
+/// Returns the minimum and maximum corners of the points.
+pub fn bounds(points: &[[f32; 2]]) -> ([f32; 2], [f32; 2]) {
+    points.iter().fold(
+        ([f32::MAX, f32::MAX], [f32::MIN, f32::MIN]),
+        |(min, max), p| ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])]),
+    )
+}
+
+/// Returns `segments + 1` points along an elliptical arc between two angles in radians, counter-clockwise from the x axis.
+pub fn ellipse_points(radius_x: f32, radius_y: f32, start: f32, end: f32, segments: usize) -> Vec<Vector2<f32>> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| {
+            let angle = start + (end - start) * i as f32 / segments as f32;
+            Vector2::new(radius_x * angle.cos(), radius_y * angle.sin())
+        })
+        .collect()
+}
+
+/// Returns the outline of a rectangle centered on the origin with rounded corners, counter-clockwise.
+///
+/// Radii are given for the top-left, top-right, bottom-right and bottom-left corners and limited to half the shorter side.
+pub fn rounded_rectangle_points(width: f32, height: f32, radii: [f32; 4], segments: usize) -> Vec<Vector2<f32>> {
+    let (half_width, half_height) = (width / 2.0, height / 2.0);
+    let limit = half_width.min(half_height);
+    let [top_left, top_right, bottom_right, bottom_left] = radii.map(|r| r.clamp(0.0, limit));
+
+    let corners = [
+        (Vector2::new(half_width - top_right, half_height - top_right), top_right, 0.0),
+        (Vector2::new(-half_width + top_left, half_height - top_left), top_left, 0.5 * PI),
+        (Vector2::new(-half_width + bottom_left, -half_height + bottom_left), bottom_left, PI),
+        (Vector2::new(half_width - bottom_right, -half_height + bottom_right), bottom_right, 1.5 * PI),
+    ];
+
+    corners.iter()
+        .flat_map(|&(center, radius, start)| {
+            let segments = if radius > 0.0 { segments } else { 1 };
+            ellipse_points(radius, radius, start, start + 0.5 * PI, segments)
+                .into_iter()
+                .map(move |p| center + p)
+        })
+        .collect()
+}
+
+/// Adds a fan of triangles from the center to each consecutive pair of points, closing the fan if requested.
+pub fn fill_fan(builder: &mut TriangleBuilder, center: Vector2<f32>, points: &[Vector2<f32>], closed: bool) {
+    if points.len() < 2 {
+        return;
+    }
+
+    let c = builder.vertex(center);
+    let rim: Vec<u16> = points.iter().map(|p| builder.vertex(*p)).collect();
+    for pair in rim.windows(2) {
+        builder.triangle(c, pair[0], pair[1]);
+    }
+    if closed {
+        builder.triangle(c, rim[rim.len() - 1], rim[0]);
+    }
+}
+
+/// Adds quads between two rows of points of the same length.
+pub fn fill_strip(builder: &mut TriangleBuilder, inner: &[Vector2<f32>], outer: &[Vector2<f32>]) {
+    for i in 1..inner.len().min(outer.len()) {
+        builder.quad(inner[i - 1], outer[i - 1], outer[i], inner[i]);
+    }
+}
+
+/// Tessellates a stroked polyline into triangles.
+pub fn stroke_polyline(builder: &mut TriangleBuilder, points: &[Vector2<f32>], closed: bool, stroke: &Stroke) {
+    let half_width = stroke.width / 2.0;
+
+    // Drop repeated points, they have no direction
+    let mut points: Vec<Vector2<f32>> = points.iter().copied().fold(Vec::new(), |mut acc, p| {
+        if acc.last().is_none_or(|last: &Vector2<f32>| (p - *last).magnitude2() > f32::EPSILON) {
+            acc.push(p);
+        }
+        acc
+    });
+    if closed && points.len() > 2 && (points[0] - points[points.len() - 1]).magnitude2() <= f32::EPSILON {
+        points.pop();
+    }
+    if points.len() < 2 || half_width <= 0.0 {
+        return;
+    }
+
+    let segment_count = if closed { points.len() } else { points.len() - 1 };
+    let segment = |i: usize| (points[i % points.len()], points[(i + 1) % points.len()]);
+    let normal = |d: Vector2<f32>| Vector2::new(-d.y, d.x);
+
+    // Corners between consecutive segments, by point index
+    let joins: Vec<Option<Join>> = (0..points.len())
+        .map(|i| {
+            if !closed && (i == 0 || i == points.len() - 1) {
+                return None;
+            }
+            let before = points[(i + points.len() - 1) % points.len()];
+            let after = points[(i + 1) % points.len()];
+            Join::new(before, points[i], after, half_width)
+        })
+        .collect();
+
+    for i in 0..segment_count {
+        let (mut a, mut b) = segment(i);
+        let direction = (b - a).normalize();
+
+        // Square caps extend the first and last segments
+        if !closed && stroke.cap == LineCap::Square {
+            if i == 0 {
+                a -= direction * half_width;
+            }
+            if i == segment_count - 1 {
+                b += direction * half_width;
+            }
+        }
+
+        // Segments end at the inner corner of their joins so they do not overlap each other
+        let offset = normal(direction) * half_width;
+        let corner = |point: Vector2<f32>, join: &Option<Join>, side: f32| match join {
+            Some(Join { inner: Some(inner), inner_side, .. }) if *inner_side == side => *inner,
+            _ => point + offset * side,
+        };
+        let (start, end) = (&joins[i % points.len()], &joins[(i + 1) % points.len()]);
+        builder.quad(corner(a, start, 1.0), corner(b, end, 1.0), corner(b, end, -1.0), corner(a, start, -1.0));
+    }
+
+    for (i, join) in joins.iter().enumerate() {
+        if let Some(join) = join {
+            add_join(builder, points[i], join, half_width, stroke);
+        }
+    }
+
+    if !closed && stroke.cap == LineCap::Round {
+        let start = (points[1] - points[0]).normalize();
+        let end = (points[points.len() - 1] - points[points.len() - 2]).normalize();
+        let start_angle = normal(start).y.atan2(normal(start).x);
+        let end_angle = normal(end).y.atan2(normal(end).x);
+        builder.fan(points[0], half_width, start_angle, start_angle + PI);
+        builder.fan(points[points.len() - 1], half_width, end_angle - PI, end_angle);
+    }
+}
+
+/// A corner of a stroked polyline.
+struct Join {
+    /// Offset directions of the incoming and outgoing segments on the outside of the turn.
+    from: Vector2<f32>,
+    to: Vector2<f32>,
+    /// Side of the segments' normals the inside of the turn is on, 1 for the left.
+    inner_side: f32,
+    /// Where the inner edges of the segments cross, or `None` if a segment is too short for them to meet within it.
+    inner: Option<Vector2<f32>>,
+}
+
+impl Join {
+
+    /// Returns the corner at `point`, or `None` if the segments continue in a straight line.
+    fn new(before: Vector2<f32>, point: Vector2<f32>, after: Vector2<f32>, half_width: f32) -> Option<Self> {
+        let previous = (point - before).normalize();
+        let next = (after - point).normalize();
+        let turn = previous.perp_dot(next);
+        if turn.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        // The gap to fill is on the outside of the turn
+        let inner_side = if turn > 0.0 { 1.0 } else { -1.0 };
+        let from = Vector2::new(previous.y, -previous.x) * inner_side;
+        let to = Vector2::new(next.y, -next.x) * inner_side;
+
+        // Each segment gives up this much of its length on the inside of the turn, at most half so neighbouring corners do not meet
+        let bisector = (from + to).normalize();
+        let inner = point - bisector * half_width / bisector.dot(from);
+        let trim = (point - inner).dot(previous);
+        let fits = trim.is_finite() && trim <= (point - before).magnitude() / 2.0 && trim <= (after - point).magnitude() / 2.0;
+
+        Some(Self { from, to, inner_side, inner: fits.then_some(inner) })
+    }
+
+}
+
+/// Fills the gap around a corner left between the segments, on the outside of the turn and,
+/// when the segments end at the inner corner, between it and the point.
+fn add_join(builder: &mut TriangleBuilder, point: Vector2<f32>, join: &Join, half_width: f32, stroke: &Stroke) {
+    let outer_from = point + join.from * half_width;
+    let outer_to = point + join.to * half_width;
+
+    if let Some(inner) = join.inner {
+        let [p, i, a, b] = [point, inner, outer_from, outer_to].map(|p| builder.vertex(p));
+        builder.triangle(i, a, p);
+        builder.triangle(i, p, b);
+    }
+
+    match stroke.join {
+        LineJoin::Round => {
+            let start = join.from.y.atan2(join.from.x);
+            let mut end = join.to.y.atan2(join.to.x);
+
+            // Take the short way around
+            if end - start > PI {
+                end -= 2.0 * PI;
+            } else if start - end > PI {
+                end += 2.0 * PI;
+            }
+            builder.fan(point, half_width, start, end);
+        }
+        LineJoin::Miter => {
+            let bisector = (join.from + join.to).normalize();
+            let length = 1.0 / bisector.dot(join.from);
+            if length.is_finite() && length <= stroke.miter_limit {
+                let c = builder.vertex(point);
+                let a = builder.vertex(outer_from);
+                let m = builder.vertex(point + bisector * half_width * length);
+                let b = builder.vertex(outer_to);
+                builder.triangle(c, a, m);
+                builder.triangle(c, m, b);
+            } else {
+                add_bevel(builder, point, outer_from, outer_to);
+            }
+        }
+        LineJoin::Bevel => add_bevel(builder, point, outer_from, outer_to),
+    }
+}
+
+fn add_bevel(builder: &mut TriangleBuilder, point: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) {
+    let [c, a, b] = [point, a, b].map(|p| builder.vertex(p));
+    builder.triangle(c, a, b);
+}
+
+/// Fills the area enclosed by the contours according to the fill rule.
+///
+/// The plane is cut into horizontal bands at every vertex and edge crossing, so no edges cross inside a band.
+/// Each band is then walked left to right, emitting a trapezoid for every span the fill rule considers inside.
+/// Trapezoids share the vertex where an edge meets a band boundary with the spans and bands on either side.
+pub fn fill_contours(builder: &mut TriangleBuilder, contours: &[Vec<Vector2<f32>>], rule: FillRule) {
+    let mut edges: Vec<Edge> = contours.iter()
+        .filter(|contour| contour.len() > 2)
+        .flat_map(|contour| (0..contour.len()).map(move |i| (contour[i], contour[(i + 1) % contour.len()])))
+        .filter_map(|(a, b)| Edge::new(a, b))
+        .collect();
+    edges.sort_by(|a, b| a.low.y.total_cmp(&b.low.y));
+
+    // Only edges starting below the top of an edge can cross it
+    let mut ys: Vec<f32> = edges.iter().flat_map(|e| [e.low.y, e.high.y]).collect();
+    for (i, a) in edges.iter().enumerate() {
+        for b in edges[i + 1..].iter().take_while(|b| b.low.y <= a.high.y) {
+            if let Some(y) = a.crossing(b) {
+                ys.push(y);
+            }
+        }
+    }
+    ys.sort_by(f32::total_cmp);
+    ys.dedup_by(|a, b| (*a - *b).abs() <= f32::EPSILON);
+
+    // Vertices by edge and band boundary, and the edges crossing the current band
+    let mut vertices: HashMap<(usize, usize), u16> = HashMap::new();
+    let mut active: Vec<usize> = Vec::new();
+    let mut next = 0;
+    for (band, boundaries) in ys.windows(2).enumerate() {
+        let mid = (boundaries[0] + boundaries[1]) / 2.0;
+        while next < edges.len() && edges[next].low.y <= mid {
+            active.push(next);
+            next += 1;
+        }
+        active.retain(|&e| edges[e].high.y >= mid);
+        active.sort_by(|&a, &b| edges[a].x_at(mid).total_cmp(&edges[b].x_at(mid)));
+
+        let mut winding = 0;
+        for pair in active.windows(2) {
+            winding += edges[pair[0]].winding;
+            let inside = match rule {
+                FillRule::NonZero => winding != 0,
+                FillRule::EvenOdd => winding % 2 != 0,
+            };
+            if inside {
+                let mut vertex = |edge: usize, boundary: usize| *vertices.entry((edge, boundary))
+                    .or_insert_with(|| builder.vertex(Vector2::new(edges[edge].x_at(ys[boundary]), ys[boundary])));
+                let [a, b, c, d] = [(pair[0], band), (pair[1], band), (pair[1], band + 1), (pair[0], band + 1)].map(|(edge, boundary)| vertex(edge, boundary));
+                builder.triangle(a, b, c);
+                builder.triangle(a, c, d);
+            }
+        }
+    }
+}
+
+/// A non-horizontal polygon edge ordered from its lowest to its highest point.
+struct Edge {
+    low: Vector2<f32>,
+    high: Vector2<f32>,
+    winding: i32,
+}
+
+impl Edge {
+
+    fn new(a: Vector2<f32>, b: Vector2<f32>) -> Option<Self> {
+        if (a.y - b.y).abs() <= f32::EPSILON {
+            return None;
+        }
+        Some(if a.y < b.y {
+            Self { low: a, high: b, winding: 1 }
+        } else {
+            Self { low: b, high: a, winding: -1 }
+        })
+    }
+
+    fn x_at(&self, y: f32) -> f32 {
+        let t = (y - self.low.y) / (self.high.y - self.low.y);
+        self.low.x + (self.high.x - self.low.x) * t
+    }
+
+    /// Returns the height at which the edges cross, if they cross between their end points.
+    fn crossing(&self, other: &Edge) -> Option<f32> {
+        let r = self.high - self.low;
+        let s = other.high - other.low;
+        let denominator = r.perp_dot(s);
+        if denominator.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let offset = other.low - self.low;
+        let t = offset.perp_dot(s) / denominator;
+        let u = offset.perp_dot(r) / denominator;
+        ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(self.low.y + r.y * t)
+    }
+
+}
+
+/// Triangulates a simple polygon with holes by ear clipping.
+///
+/// Holes are joined to the outline with bridge edges, turning the polygon into a single contour that is then clipped ear by ear.
+pub fn triangulate_polygon(builder: &mut TriangleBuilder, outline: &[Vector2<f32>], holes: &[Vec<Vector2<f32>>]) {
+    let mut contour = with_orientation(outline, true);
+    if contour.len() < 3 {
+        return;
+    }
+
+    // Bridge the holes furthest to the right first so later bridges cannot cross them
+    let mut holes: Vec<Vec<Vector2<f32>>> = holes.iter()
+        .map(|hole| with_orientation(hole, false))
+        .filter(|hole| hole.len() > 2)
+        .collect();
+    holes.sort_by(|a, b| max_x(b).total_cmp(&max_x(a)));
+    for i in 0..holes.len() {
+        bridge_hole(&mut contour, &holes[i], &holes[i + 1..]);
+    }
+
+    let indices: Vec<u16> = contour.iter().map(|p| builder.vertex(*p)).collect();
+    let mut remaining: Vec<usize> = (0..contour.len()).collect();
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&i| {
+            let [a, b, c] = [remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]];
+            is_ear(&contour, &remaining, a, b, c)
+        });
+
+        let Some(i) = ear else {
+            warn!("Polygon could not be fully triangulated, it may be self-intersecting");
+            return;
+        };
+
+        let [a, b, c] = [remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]];
+        builder.triangle(indices[a], indices[b], indices[c]);
+        remaining.remove(i);
+    }
+
+    if remaining.len() == 3 {
+        builder.triangle(indices[remaining[0]], indices[remaining[1]], indices[remaining[2]]);
+    }
+}
+
+/// Returns the points without repeats, wound counter-clockwise or clockwise.
+fn with_orientation(points: &[Vector2<f32>], counter_clockwise: bool) -> Vec<Vector2<f32>> {
+    let mut points: Vec<Vector2<f32>> = points.to_vec();
+    points.dedup_by(|a, b| (*a - *b).magnitude2() <= f32::EPSILON);
+    if points.len() > 1 && (points[0] - points[points.len() - 1]).magnitude2() <= f32::EPSILON {
+        points.pop();
+    }
+
+    let area: f32 = (0..points.len()).map(|i| points[i].perp_dot(points[(i + 1) % points.len()])).sum();
+    if (area > 0.0) != counter_clockwise {
+        points.reverse();
+    }
+    points
+}
+
+fn max_x(points: &[Vector2<f32>]) -> f32 {
+    points.iter().map(|p| p.x).fold(f32::MIN, f32::max)
+}
+
+/// Splices the hole into the contour through the closest contour vertex its rightmost point can see.
+fn bridge_hole(contour: &mut Vec<Vector2<f32>>, hole: &[Vector2<f32>], other_holes: &[Vec<Vector2<f32>>]) {
+    let start = (0..hole.len()).max_by(|&a, &b| hole[a].x.total_cmp(&hole[b].x)).unwrap_or(0);
+    let from = hole[start];
+
+    let mut candidates: Vec<usize> = (0..contour.len()).collect();
+    candidates.sort_by(|&a, &b| (contour[a] - from).magnitude2().total_cmp(&(contour[b] - from).magnitude2()));
+
+    let edges = |points: &[Vector2<f32>]| -> Vec<(Vector2<f32>, Vector2<f32>)> {
+        (0..points.len()).map(|i| (points[i], points[(i + 1) % points.len()])).collect()
+    };
+    let mut obstacles = edges(contour);
+    obstacles.extend(edges(hole));
+    for other in other_holes {
+        obstacles.extend(edges(other));
+    }
+
+    let target = candidates.iter().copied()
+        .find(|&i| !obstacles.iter().any(|&(a, b)| segments_cross(from, contour[i], a, b)))
+        .unwrap_or(candidates[0]);
+
+    let mut bridged = Vec::with_capacity(contour.len() + hole.len() + 2);
+    bridged.extend_from_slice(&contour[..=target]);
+    bridged.extend(hole[start..].iter().chain(&hole[..=start]));
+    bridged.extend_from_slice(&contour[target..]);
+    *contour = bridged;
+}
+
+/// Returns true if the segments cross at a point other than their end points.
+fn segments_cross(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, d: Vector2<f32>) -> bool {
+    let side = |p: Vector2<f32>, q: Vector2<f32>, r: Vector2<f32>| (q - p).perp_dot(r - p);
+    let (d1, d2) = (side(a, b, c), side(a, b, d));
+    let (d3, d4) = (side(c, d, a), side(c, d, b));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+/// Returns true if the triangle at `b` is convex and contains none of the other remaining points.
+fn is_ear(points: &[Vector2<f32>], remaining: &[usize], a: usize, b: usize, c: usize) -> bool {
+    let (pa, pb, pc) = (points[a], points[b], points[c]);
+    if (pb - pa).perp_dot(pc - pb) <= 0.0 {
+        return false;
+    }
+
+    remaining.iter()
+        .map(|&i| points[i])
+        .filter(|p| *p != pa && *p != pb && *p != pc)
+        .all(|p| !in_triangle(p, pa, pb, pc))
+}
+
+fn in_triangle(p: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>) -> bool {
+    (b - a).perp_dot(p - a) >= 0.0 && (c - b).perp_dot(p - b) >= 0.0 && (a - c).perp_dot(p - c) >= 0.0
+}
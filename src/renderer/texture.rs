@@ -1,26 +1,28 @@
-use image::{DynamicImage, RgbaImage};
-use wgpu::{Device, TextureFormat};
+use wgpu::{Device, TextureFormat, TextureUsages};
 
+/// A GPU texture with a view and sampler, either uploaded from an image or rendered into.
 #[derive(Clone, Debug)]
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
-    pub image: RgbaImage
 }
 
 impl Texture {
 
-    pub fn from_image(device: &Device, dimg: DynamicImage) -> Self {
-        let img = dimg.to_rgba8();
+    /// Creates a texture that images can be written into.
+    pub fn new(device: &Device, width: u32, height: u32) -> Self {
+        Self::create(device, "Texture", width, height, TextureFormat::Rgba8UnormSrgb, TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING)
+    }
 
-        Texture::new(device, img)
+    /// Creates a texture that can be rendered into and then sampled.
+    pub fn render_target(device: &Device, width: u32, height: u32, format: TextureFormat) -> Self {
+        Self::create(device, "Render Target Texture", width, height, format, TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING)
     }
 
-    pub fn new(device: &Device, image: RgbaImage) -> Self {
-        let (width, height) = image.dimensions();
+    fn create(device: &Device, label: &str, width: u32, height: u32, format: TextureFormat, usage: TextureUsages) -> Self {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Texture"),
+            label: Some(label),
             size: wgpu::Extent3d {
                 width,
                 height,
@@ -29,8 +31,8 @@ impl Texture {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            format,
+            usage,
             view_formats: &[],
         });
 
@@ -50,9 +52,7 @@ impl Texture {
             texture,
             view,
             sampler,
-            image
         }
-
     }
 
-}
\ No newline at end of file
+}
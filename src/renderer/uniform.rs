@@ -1,6 +1,7 @@
-use crate::graphics::transform::{ModelMatrix, Transform};
+use crate::graphics::light::{Light, LightKind, Lighting, MAX_LIGHTS};
+use crate::graphics::material::{Material3D, Shading, MAX_MATERIAL_PARAMS};
 use bytemuck::{Pod, Zeroable};
-use cgmath::{Matrix4, SquareMatrix};
+use cgmath::{InnerSpace, Matrix, Matrix4, SquareMatrix, Vector4};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Pod, Zeroable)]
@@ -25,11 +26,134 @@ impl Uniform2D {
         self.camera = camera_matrix;
     }
 
-    pub fn update_transform(&mut self, transform: &Transform) {
-        self.transform = transform.to_matrix().into();
+    pub fn update_transform(&mut self, matrix: Matrix4<f32>) {
+        self.transform = matrix.into();
     }
 
     pub fn set_use_texture(&mut self, use_texture: bool) {
         self.use_texture = if use_texture { 1 } else { 0 };
     }
-}
\ No newline at end of file
+}
+/// Uniform block of a post effect, laid out like `PostUniform` in the post effect prelude.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct PostUniform {
+    resolution: [f32; 2],
+    _padding: [f32; 2],
+    params: [f32; 4],
+}
+
+impl PostUniform {
+    pub fn new(width: u32, height: u32, params: [f32; 4]) -> Self {
+        Self {
+            resolution: [width as f32, height as f32],
+            _padding: [0.0; 2],
+            params,
+        }
+    }
+}
+
+/// Uniform block of a material, laid out like `MaterialUniform` in the material prelude.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct MaterialUniform {
+    params: [f32; MAX_MATERIAL_PARAMS],
+}
+
+impl MaterialUniform {
+    pub fn new(params: [f32; MAX_MATERIAL_PARAMS]) -> Self {
+        Self { params }
+    }
+}
+
+/// Uniform block of a 3D draw, laid out like `DrawUniform` in the 3D shader.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct Uniform3D {
+    camera: [[f32; 4]; 4],
+    model: [[f32; 4]; 4],
+    /// Inverse transpose of the model matrix, which keeps normals perpendicular under non-uniform scaling.
+    normal: [[f32; 4]; 4],
+    color: [f32; 4],
+    /// Camera position with w set to 1, or the direction towards an orthographic camera with w set to 0.
+    eye: [f32; 4],
+    ambient: [f32; 4],
+    /// Specular strength and shininess for Blinn-Phong, metallic and roughness for PBR.
+    shading_params: [f32; 4],
+    use_texture: u32,
+    shading: u32,
+    light_count: u32,
+    _padding: u32,
+    lights: [LightUniform; MAX_LIGHTS],
+}
+
+impl Uniform3D {
+    pub fn new(camera_matrix: [[f32; 4]; 4], model: Matrix4<f32>, material: &Material3D, use_texture: bool, lighting: Option<&Lighting>) -> Self {
+        let normal = model.invert().map(|inverse| inverse.transpose()).unwrap_or(model);
+
+        // The camera projects onto its position, or infinitely far along its view for orthographic projections
+        let camera = Matrix4::from(camera_matrix);
+        let eye = camera.invert().map(|inverse| inverse * Vector4::unit_z()).unwrap_or(Vector4::unit_z());
+        let eye = if eye.w.abs() > f32::EPSILON { (eye.truncate() / eye.w).extend(1.0) } else { (-eye.truncate().normalize()).extend(0.0) };
+
+        let (shading, shading_params) = match (lighting, material.shading) {
+            (None, _) | (_, Shading::Unlit) => (0, [0.0; 4]),
+            (_, Shading::BlinnPhong { specular, shininess }) => (1, [specular, shininess.max(1.0), 0.0, 0.0]),
+            (_, Shading::Pbr { metallic, roughness }) => (2, [metallic.clamp(0.0, 1.0), roughness.clamp(0.04, 1.0), 0.0, 0.0]),
+        };
+        let lights = lighting.map_or(&[][..], |lighting| &lighting.lights[..lighting.lights.len().min(MAX_LIGHTS)]);
+        let mut light_uniforms = [LightUniform::zeroed(); MAX_LIGHTS];
+        for (uniform, light) in light_uniforms.iter_mut().zip(lights) {
+            *uniform = LightUniform::new(light);
+        }
+
+        Self {
+            camera: camera_matrix,
+            model: model.into(),
+            normal: normal.into(),
+            color: material.color.into(),
+            eye: eye.into(),
+            ambient: lighting.map_or([0.0; 4], |lighting| lighting.ambient.into()),
+            shading_params,
+            use_texture: if use_texture { 1 } else { 0 },
+            shading,
+            light_count: lights.len() as u32,
+            _padding: 0,
+            lights: light_uniforms,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct LightUniform {
+    position: [f32; 3],
+    range: f32,
+    direction: [f32; 3],
+    kind: u32,
+    /// Color multiplied by the intensity.
+    color: [f32; 3],
+    cos_inner: f32,
+    cos_outer: f32,
+    _padding: [f32; 3],
+}
+
+impl LightUniform {
+    fn new(light: &Light) -> Self {
+        let color = [light.color.r, light.color.g, light.color.b].map(|c| c * light.intensity);
+        let uniform = Self { color, ..Self::zeroed() };
+        match light.kind {
+            LightKind::Directional { direction } => Self { direction: direction.into(), kind: 0, ..uniform },
+            LightKind::Point { position, range } => Self { position: position.into(), range, kind: 1, ..uniform },
+            LightKind::Spot { position, direction, range, inner_angle, outer_angle } => Self {
+                position: position.into(),
+                range,
+                direction: direction.into(),
+                kind: 2,
+                cos_inner: inner_angle.min(outer_angle).to_radians().cos(),
+                cos_outer: outer_angle.to_radians().cos(),
+                ..uniform
+            },
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::graphics::color::Color;
+use crate::graphics::transform::{ModelMatrix, Transform};
 use bytemuck::{Pod, Zeroable};
 use wgpu::vertex_attr_array;
 
@@ -26,6 +28,62 @@ impl Vertex2D {
     }
 }
 
+/// Per-instance data for instanced drawing, read from a second vertex buffer.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct InstanceData {
+    pub model: [[f32; 4]; 4],
+    pub tint: [f32; 4],
+    /// Offset (x, y) and size (z, w) of the region of the texture mapped onto the mesh.
+    pub uv_rect: [f32; 4]
+}
+
+impl Default for InstanceData {
+    fn default() -> Self {
+        Self::new(Transform::default())
+    }
+}
+
+impl InstanceData {
+
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = vertex_attr_array![
+        3 => Float32x4, // model column 0
+        4 => Float32x4, // model column 1
+        5 => Float32x4, // model column 2
+        6 => Float32x4, // model column 3
+        7 => Float32x4, // tint
+        8 => Float32x4  // uv rect
+    ];
+
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            model: transform.to_matrix().into(),
+            tint: Color::WHITE.into(),
+            uv_rect: [0.0, 0.0, 1.0, 1.0]
+        }
+    }
+
+    /// Sets the color the mesh colors are multiplied with.
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint.into();
+        self
+    }
+
+    /// Sets the region of the texture in normalized coordinates.
+    pub fn with_uv_rect(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.uv_rect = [x, y, width, height];
+        self
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<InstanceData>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Vertex3D {
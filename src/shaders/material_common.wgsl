@@ -0,0 +1,51 @@
+// Shared by every material. Materials define `fs_main` and optionally `vs_main`.
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct DrawUniform {
+    camera: mat4x4<f32>,
+    transform: mat4x4<f32>,
+    use_texture: u32,
+};
+
+struct MaterialUniform {
+    params: array<vec4<f32>, 4>,
+};
+
+@group(0) @binding(0) var<uniform> draw_uniform: DrawUniform;
+@group(1) @binding(0) var main_texture: texture_2d<f32>;
+@group(1) @binding(1) var main_sampler: sampler;
+@group(2) @binding(0) var<uniform> material: MaterialUniform;
+@group(3) @binding(0) var texture_0: texture_2d<f32>;
+@group(3) @binding(1) var sampler_0: sampler;
+@group(3) @binding(2) var texture_1: texture_2d<f32>;
+@group(3) @binding(3) var sampler_1: sampler;
+@group(3) @binding(4) var texture_2: texture_2d<f32>;
+@group(3) @binding(5) var sampler_2: sampler;
+@group(3) @binding(6) var texture_3: texture_2d<f32>;
+@group(3) @binding(7) var sampler_3: sampler;
+
+// Places the vertex like the built-in shader does.
+fn default_vertex(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = draw_uniform.camera * draw_uniform.transform * vec4<f32>(in.position, 1.0, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+// Returns the vertex color, multiplied by the style's image if it has one.
+fn base_color(in: VertexOutput) -> vec4<f32> {
+    let texel = textureSample(main_texture, main_sampler, in.uv);
+    return select(in.color, in.color * texel, draw_uniform.use_texture == 1u);
+}
@@ -0,0 +1,23 @@
+// params.x: brightness above which colors start to glow.
+// params.y: strength of the glow.
+// params.z: glow radius in pixels.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, in.uv);
+    let step = post.params.z / 3.0 / post.resolution;
+    var glow = vec3<f32>(0.0);
+    var total = 0.0;
+
+    // Blur only the part of each color above the threshold
+    for (var y = -3; y <= 3; y++) {
+        for (var x = -3; x <= 3; x++) {
+            let weight = exp(-f32(x * x + y * y) / 4.5);
+            let tap = textureSampleLevel(source_texture, source_sampler, in.uv + vec2<f32>(f32(x), f32(y)) * step, 0.0).rgb;
+            glow += max(tap - vec3<f32>(post.params.x), vec3<f32>(0.0)) * weight;
+            total += weight;
+        }
+    }
+
+    return vec4<f32>(color.rgb + glow / total * post.params.y, color.a);
+}
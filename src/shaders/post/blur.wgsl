@@ -0,0 +1,19 @@
+// params.x: blur radius in pixels.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let step = post.params.x / 3.0 / post.resolution;
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+
+    // Gaussian weighted 7x7 taps spread over the radius
+    for (var y = -3; y <= 3; y++) {
+        for (var x = -3; x <= 3; x++) {
+            let weight = exp(-f32(x * x + y * y) / 4.5);
+            sum += textureSampleLevel(source_texture, source_sampler, in.uv + vec2<f32>(f32(x), f32(y)) * step, 0.0) * weight;
+            total += weight;
+        }
+    }
+
+    return sum / total;
+}
@@ -0,0 +1,35 @@
+// Shared by every post effect. Effects define `fs_main`, reading the finished frame from `source_texture`.
+
+struct PostUniform {
+    // Size of the frame in pixels.
+    resolution: vec2<f32>,
+    // Parameters of the effect, their meaning is up to the effect.
+    params: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> post: PostUniform;
+@group(0) @binding(1) var source_texture: texture_2d<f32>;
+@group(0) @binding(2) var source_sampler: sampler;
+@group(0) @binding(3) var extra_texture: texture_2d<f32>;
+@group(0) @binding(4) var extra_sampler: sampler;
+
+// Draws a single triangle covering the whole frame, with uv (0, 0) at the top-left corner.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
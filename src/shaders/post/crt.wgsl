@@ -0,0 +1,17 @@
+// params.x: how dark every other row of pixels gets, from 0 to 1.
+// params.y: how much the picture bulges as if on a curved screen.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Push pixels outwards the further they are from the center
+    var uv = in.uv * 2.0 - 1.0;
+    uv += uv * (uv.yx * uv.yx) * post.params.y;
+    uv = uv * 0.5 + 0.5;
+
+    let color = textureSampleLevel(source_texture, source_sampler, uv, 0.0);
+    let row = uv.y * post.resolution.y;
+    let scanline = 1.0 - post.params.x * (0.5 - 0.5 * sin(row * 3.14159265));
+
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(color.rgb * scanline, color.a), inside);
+}
@@ -0,0 +1,8 @@
+// params.x: how much of the color is replaced by its luminance, from 0 to 1.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, in.uv);
+    let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return vec4<f32>(mix(color.rgb, vec3<f32>(luminance), post.params.x), color.a);
+}
@@ -0,0 +1,24 @@
+// extra_texture: color lookup table with N slices of N x N texels side by side, blue increasing per slice,
+// red increasing to the right and green increasing downwards within a slice.
+// params.x: how much of the graded color replaces the original, from 0 to 1.
+
+fn lut_sample(encoded: vec3<f32>, slice: f32, size: f32) -> vec3<f32> {
+    // Stay on texel centers so filtering never blends neighbouring slices
+    let texel = encoded.rg * (size - 1.0) + 0.5;
+    let uv = vec2<f32>((slice * size + texel.x) / (size * size), texel.y / size);
+    return textureSampleLevel(extra_texture, extra_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, in.uv);
+    let size = f32(textureDimensions(extra_texture).y);
+
+    // Tables are indexed by the color as stored in an image, not in linear space
+    let encoded = clamp(linear_to_srgb(color.rgb), vec3<f32>(0.0), vec3<f32>(1.0));
+    let blue = encoded.b * (size - 1.0);
+    let slice = floor(blue);
+    let graded = mix(lut_sample(encoded, slice, size), lut_sample(encoded, min(slice + 1.0, size - 1.0), size), blue - slice);
+
+    return vec4<f32>(mix(color.rgb, graded, post.params.x), color.a);
+}
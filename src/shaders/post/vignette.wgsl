@@ -0,0 +1,11 @@
+// params.x: how dark the corners get, from 0 to 1.
+// params.y: distance from the center where darkening starts, 1 being the middle of the edges.
+// params.z: distance over which the darkening fades in.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, in.uv);
+    let distance = length(in.uv - 0.5) * 2.0;
+    let darkening = smoothstep(post.params.y, post.params.y + post.params.z, distance) * post.params.x;
+    return vec4<f32>(color.rgb * (1.0 - darkening), color.a);
+}
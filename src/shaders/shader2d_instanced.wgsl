@@ -0,0 +1,59 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct InstanceInput {
+    @location(3) model_0: vec4<f32>,
+    @location(4) model_1: vec4<f32>,
+    @location(5) model_2: vec4<f32>,
+    @location(6) model_3: vec4<f32>,
+    @location(7) tint: vec4<f32>,
+    @location(8) uv_rect: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct DrawUniform {
+    camera: mat4x4<f32>,
+    transform: mat4x4<f32>,
+    use_texture: u32,
+};
+
+@group(0) @binding(0) var<uniform> draw_uniform: DrawUniform;
+@group(1) @binding(0) var my_texture: texture_2d<f32>;
+@group(1) @binding(1) var my_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Apply the instance model matrix, then the draw transform.
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    let world_position = draw_uniform.transform * model * vec4<f32>(in.position, 1.0, 1.0);
+    out.clip_position = draw_uniform.camera * world_position;
+
+    // Tint the vertex color per instance.
+    out.color = in.color * instance.tint;
+
+    // Map the UV coordinates into the instance's region of the texture.
+    out.uv = instance.uv_rect.xy + in.uv * instance.uv_rect.zw;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var final_color = in.color;
+
+    if (draw_uniform.use_texture == 1u) {
+        final_color = in.color * textureSample(my_texture, my_sampler, in.uv);
+    }
+
+    return final_color;
+}
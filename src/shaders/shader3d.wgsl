@@ -0,0 +1,144 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct Light {
+    position: vec3<f32>,
+    range: f32,
+    direction: vec3<f32>,
+    // 0 for directional, 1 for point and 2 for spot lights.
+    kind: u32,
+    color: vec3<f32>,
+    cos_inner: f32,
+    cos_outer: f32,
+};
+
+struct DrawUniform {
+    camera: mat4x4<f32>,
+    model: mat4x4<f32>,
+    normal: mat4x4<f32>,
+    color: vec4<f32>,
+    eye: vec4<f32>,
+    ambient: vec4<f32>,
+    shading_params: vec4<f32>,
+    use_texture: u32,
+    // 0 for unlit, 1 for Blinn-Phong and 2 for PBR.
+    shading: u32,
+    light_count: u32,
+    lights: array<Light, 8>,
+};
+
+struct LightSample {
+    direction: vec3<f32>,
+    radiance: vec3<f32>,
+};
+
+const PI: f32 = 3.14159265;
+
+@group(0) @binding(0) var<uniform> draw_uniform: DrawUniform;
+@group(1) @binding(0) var my_texture: texture_2d<f32>;
+@group(1) @binding(1) var my_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Place the vertex in the world, then project it with the camera.
+    let world_position = draw_uniform.model * vec4<f32>(in.position, 1.0);
+    out.clip_position = draw_uniform.camera * world_position;
+    out.world_position = world_position.xyz;
+
+    // Normals use the inverse transpose of the model matrix so they stay perpendicular to the surface.
+    out.normal = (draw_uniform.normal * vec4<f32>(in.normal, 0.0)).xyz;
+    out.uv = in.uv;
+
+    return out;
+}
+
+// Returns the direction towards the light and the light arriving from it at the position.
+fn sample_light(light: Light, position: vec3<f32>) -> LightSample {
+    if (light.kind == 0u) {
+        return LightSample(-normalize(light.direction), light.color);
+    }
+
+    let to_light = light.position - position;
+    let distance = length(to_light);
+    let direction = to_light / max(distance, 1e-4);
+    let falloff = clamp(1.0 - distance / max(light.range, 1e-4), 0.0, 1.0);
+    var radiance = light.color * falloff * falloff;
+
+    if (light.kind == 2u) {
+        let cos_angle = dot(-direction, normalize(light.direction));
+        radiance = radiance * smoothstep(light.cos_outer, max(light.cos_inner, light.cos_outer + 1e-4), cos_angle);
+    }
+    return LightSample(direction, radiance);
+}
+
+fn blinn_phong(base: vec3<f32>, n: vec3<f32>, v: vec3<f32>, light: LightSample) -> vec3<f32> {
+    let n_dot_l = max(dot(n, light.direction), 0.0);
+    let h = normalize(light.direction + v);
+    let specular = select(0.0, draw_uniform.shading_params.x * pow(max(dot(n, h), 0.0), draw_uniform.shading_params.y), n_dot_l > 0.0);
+    return light.radiance * (base * n_dot_l + vec3<f32>(specular));
+}
+
+// Cook-Torrance with a GGX distribution, scaled by pi so a white light facing a rough surface shows its base color.
+fn pbr(base: vec3<f32>, n: vec3<f32>, v: vec3<f32>, light: LightSample) -> vec3<f32> {
+    let metallic = draw_uniform.shading_params.x;
+    let roughness = draw_uniform.shading_params.y;
+    let h = normalize(light.direction + v);
+    let n_dot_l = max(dot(n, light.direction), 0.0);
+    let n_dot_v = max(dot(n, v), 1e-4);
+    let n_dot_h = max(dot(n, h), 0.0);
+    let v_dot_h = max(dot(v, h), 0.0);
+
+    let a2 = pow(roughness, 4.0);
+    let d = a2 / (PI * pow(n_dot_h * n_dot_h * (a2 - 1.0) + 1.0, 2.0));
+    let k = pow(roughness + 1.0, 2.0) / 8.0;
+    let g = n_dot_l / (n_dot_l * (1.0 - k) + k) * n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let f0 = mix(vec3<f32>(0.04), base, metallic);
+    let f = f0 + (1.0 - f0) * pow(1.0 - v_dot_h, 5.0);
+
+    let specular = d * g * f / max(4.0 * n_dot_l * n_dot_v, 1e-4);
+    let diffuse = (1.0 - f) * (1.0 - metallic) * base / PI;
+    return (diffuse + specular) * light.radiance * n_dot_l * PI;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var base = draw_uniform.color;
+
+    if (draw_uniform.use_texture == 1u) {
+        base = base * textureSample(my_texture, my_sampler, in.uv);
+    }
+
+    if (draw_uniform.shading == 0u) {
+        return base;
+    }
+
+    let n = normalize(in.normal);
+    var v = draw_uniform.eye.xyz;
+    if (draw_uniform.eye.w > 0.5) {
+        v = normalize(draw_uniform.eye.xyz - in.world_position);
+    }
+
+    var color = base.rgb * draw_uniform.ambient.rgb;
+    for (var i = 0u; i < draw_uniform.light_count; i++) {
+        let light = sample_light(draw_uniform.lights[i], in.world_position);
+        if (draw_uniform.shading == 1u) {
+            color += blinn_phong(base.rgb, n, v, light);
+        } else {
+            color += pbr(base.rgb, n, v, light);
+        }
+    }
+
+    return vec4<f32>(color, base.a);
+}
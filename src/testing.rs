@@ -0,0 +1,179 @@
+use crate::graphics::canvas::Canvas;
+use crate::graphics::image::Image;
+use crate::headless::HeadlessRenderer;
+use image::{Rgba, RgbaImage};
+use log::warn;
+use std::path::{Path, PathBuf};
+
+/// Set this environment variable to write stored references from the current output, creating missing ones.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "RGFX_UPDATE_SNAPSHOTS";
+
+/// Set this environment variable to skip rendering tests on machines without any GPU or software adapter.
+pub const SKIP_GPU_TESTS_ENV: &str = "RGFX_SKIP_GPU_TESTS";
+
+/// Compares rendered frames against reference PNGs stored on disk.
+///
+/// A missing reference fails the comparison, references are only written when [`UPDATE_SNAPSHOTS_ENV`] is set.
+/// On mismatch the actual frame and a diff image highlighting the differing pixels in red are written next to the reference.
+#[derive(Clone, Debug)]
+pub struct GoldenImage {
+    name: String,
+    dir: PathBuf,
+    tolerance: u8,
+    max_mismatched_pixels: usize,
+}
+
+impl GoldenImage {
+
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            dir: PathBuf::from("tests/snapshots"),
+            tolerance: 2,
+            max_mismatched_pixels: 0,
+        }
+    }
+
+    /// Sets the directory the reference images are stored in.
+    pub fn with_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Sets the maximum difference allowed per color channel before a pixel counts as mismatched.
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets how many pixels may exceed the tolerance before the comparison fails.
+    pub fn with_max_mismatched_pixels(mut self, count: usize) -> Self {
+        self.max_mismatched_pixels = count;
+        self
+    }
+
+    pub fn reference_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.png", self.name))
+    }
+
+    pub fn actual_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.actual.png", self.name))
+    }
+
+    pub fn diff_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.diff.png", self.name))
+    }
+
+    /// Compares the image against the stored reference.
+    pub fn compare(&self, image: &Image) -> Result<(), String> {
+        let actual = image.image.to_rgba8();
+        let reference_path = self.reference_path();
+
+        if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+            return self.update(image);
+        }
+        if !reference_path.exists() {
+            self.write_failure(&actual, None)?;
+            return Err(format!(
+                "Snapshot '{}' has no reference at {}, run with {}=1 to create it",
+                self.name, reference_path.display(), UPDATE_SNAPSHOTS_ENV
+            ));
+        }
+
+        let reference = image::open(&reference_path)
+            .map_err(|e| format!("Unable to read {}: {}", reference_path.display(), e))?
+            .to_rgba8();
+
+        if reference.dimensions() != actual.dimensions() {
+            self.write_failure(&actual, None)?;
+            return Err(format!(
+                "Snapshot '{}' has size {:?} but the reference is {:?}",
+                self.name, actual.dimensions(), reference.dimensions()
+            ));
+        }
+
+        let (diff, mismatched) = diff_images(&reference, &actual, self.tolerance);
+        if mismatched > self.max_mismatched_pixels {
+            self.write_failure(&actual, Some(&diff))?;
+            return Err(format!(
+                "Snapshot '{}' differs from the reference in {} pixels (allowed {}), see {}",
+                self.name, mismatched, self.max_mismatched_pixels, self.diff_path().display()
+            ));
+        }
+
+        // Clean up artifacts from an earlier failing run
+        let _ = std::fs::remove_file(self.actual_path());
+        let _ = std::fs::remove_file(self.diff_path());
+
+        Ok(())
+    }
+
+    /// Writes the image as the stored reference.
+    pub fn update(&self, image: &Image) -> Result<(), String> {
+        let reference_path = self.reference_path();
+        warn!("Writing reference image {}", reference_path.display());
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("Unable to create {}: {}", self.dir.display(), e))?;
+        image.image.to_rgba8().save(&reference_path).map_err(|e| format!("Unable to write {}: {}", reference_path.display(), e))
+    }
+
+    /// Compares the image against the stored reference and panics on mismatch.
+    pub fn assert(&self, image: &Image) {
+        if let Err(e) = self.compare(image) {
+            panic!("{}", e);
+        }
+    }
+
+    fn write_failure(&self, actual: &RgbaImage, diff: Option<&RgbaImage>) -> Result<(), String> {
+        actual.save(self.actual_path()).map_err(|e| format!("Unable to write {}: {}", self.actual_path().display(), e))?;
+        if let Some(diff) = diff {
+            diff.save(self.diff_path()).map_err(|e| format!("Unable to write {}: {}", self.diff_path().display(), e))?;
+        }
+        Ok(())
+    }
+
+}
+
+/// Builds a diff image and counts the pixels whose channels differ by more than the tolerance.
+/// Matching pixels are drawn as a faded grayscale copy of the reference, mismatched pixels in red.
+pub fn diff_images(reference: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (RgbaImage, usize) {
+    let mut diff = RgbaImage::new(reference.width(), reference.height());
+    let mut mismatched = 0;
+
+    for (x, y, expected) in reference.enumerate_pixels() {
+        let got = actual.get_pixel(x, y);
+        let exceeds = expected.0.iter().zip(got.0.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance);
+
+        let pixel = if exceeds {
+            mismatched += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (expected[0] as u32 * 299 + expected[1] as u32 * 587 + expected[2] as u32 * 114) / 1000;
+            let faded = (luma / 4 + 191) as u8;
+            Rgba([faded, faded, faded, 255])
+        };
+        diff.put_pixel(x, y, pixel);
+    }
+
+    (diff, mismatched)
+}
+
+/// Creates a headless renderer, panicking when no adapter is available.
+///
+/// Returns `None` only when [`SKIP_GPU_TESTS_ENV`] is set and no adapter is available, so tests can be skipped on purpose.
+pub fn headless_renderer(width: u32, height: u32) -> Option<HeadlessRenderer> {
+    match HeadlessRenderer::new(width, height) {
+        Ok(renderer) => Some(renderer),
+        Err(e) if std::env::var_os(SKIP_GPU_TESTS_ENV).is_some() => {
+            warn!("Skipping headless rendering: {}", e);
+            None
+        }
+        Err(e) => panic!("Unable to create a headless renderer, set {} to skip rendering tests: {}", SKIP_GPU_TESTS_ENV, e),
+    }
+}
+
+/// Renders the frame drawn by the closure on a fresh canvas sized to the renderer.
+pub fn render_frame<F: FnOnce(&mut Canvas)>(renderer: &mut HeadlessRenderer, draw: F) -> Image {
+    let mut canvas = renderer.canvas();
+    draw(&mut canvas);
+    renderer.render(&canvas).expect("Failed to render frame")
+}
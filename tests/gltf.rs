@@ -0,0 +1,213 @@
+use rgfx::prelude::*;
+use rgfx::testing::{headless_renderer, render_frame};
+use cgmath::{Deg, InnerSpace, Quaternion, Rad, Rotation3};
+use std::path::PathBuf;
+
+fn directory(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("rgfx_gltf_{}_{}", test, std::process::id()));
+    std::fs::create_dir_all(&directory).expect("Failed to create directory");
+    directory
+}
+
+/// A unit square facing +z, with normals and texture coordinates, followed by its indices.
+fn quad_buffer() -> Vec<u8> {
+    let positions = [[-0.5f32, -0.5, 0.0], [0.5, -0.5, 0.0], [0.5, 0.5, 0.0], [-0.5, 0.5, 0.0]];
+    let uvs = [[0.0f32, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+    let mut buffer = Vec::new();
+    positions.iter().flatten().for_each(|v| buffer.extend(v.to_le_bytes()));
+    positions.iter().for_each(|_| [0.0f32, 0.0, 1.0].iter().for_each(|v| buffer.extend(v.to_le_bytes())));
+    uvs.iter().flatten().for_each(|v| buffer.extend(v.to_le_bytes()));
+    [0u16, 1, 2, 0, 2, 3].iter().for_each(|i| buffer.extend(i.to_le_bytes()));
+    buffer
+}
+
+/// A scene with a textured quad under a rotated parent, and two cameras.
+fn scene_json(buffer_uri: Option<&str>, extra_attributes: &str) -> String {
+    let uri = buffer_uri.map(|uri| format!(r#""uri": "{}", "#, uri)).unwrap_or_default();
+    format!(r#"{{
+        "asset": {{ "version": "2.0" }},
+        "scene": 0,
+        "scenes": [{{ "nodes": [0, 2, 3] }}],
+        "nodes": [
+            {{ "name": "parent", "translation": [1, 0, 0], "rotation": [0, 0, 0.70710677, 0.70710677], "children": [1] }},
+            {{ "name": "child", "mesh": 0, "scale": [2, 2, 2] }},
+            {{ "name": "camera", "camera": 0, "translation": [0, 0, 5] }},
+            {{ "name": "top", "camera": 1, "translation": [0, 10, 0], "rotation": [-0.70710677, 0, 0, 0.70710677] }}
+        ],
+        "cameras": [
+            {{ "type": "perspective", "perspective": {{ "yfov": 0.8, "znear": 0.1, "zfar": 50, "aspectRatio": 1.5 }} }},
+            {{ "type": "orthographic", "orthographic": {{ "xmag": 4, "ymag": 2, "znear": 0.5, "zfar": 20 }} }}
+        ],
+        "meshes": [{{ "name": "quad", "primitives": [{{
+            "attributes": {{ "POSITION": 0, {} "TEXCOORD_0": 2 }},
+            "indices": 3,
+            "material": 0
+        }}] }}],
+        "materials": [{{ "pbrMetallicRoughness": {{ "baseColorFactor": [1, 1, 1, 0.5], "baseColorTexture": {{ "index": 0 }}, "metallicFactor": 0.25, "roughnessFactor": 0.5 }} }}],
+        "textures": [{{ "source": 0 }}],
+        "images": [{{ "uri": "texture.png" }}],
+        "buffers": [{{ {}"byteLength": 140 }}],
+        "bufferViews": [
+            {{ "buffer": 0, "byteOffset": 0, "byteLength": 128 }},
+            {{ "buffer": 0, "byteOffset": 128, "byteLength": 12 }}
+        ],
+        "accessors": [
+            {{ "bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 4, "type": "VEC3", "min": [-0.5, -0.5, 0], "max": [0.5, 0.5, 0] }},
+            {{ "bufferView": 0, "byteOffset": 48, "componentType": 5126, "count": 4, "type": "VEC3" }},
+            {{ "bufferView": 0, "byteOffset": 96, "componentType": 5126, "count": 4, "type": "VEC2" }},
+            {{ "bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR" }}
+        ]
+    }}"#, extra_attributes, uri)
+}
+
+fn write_texture(directory: &std::path::Path) {
+    image::RgbaImage::from_pixel(2, 2, image::Rgba([0, 255, 0, 255])).save(directory.join("texture.png")).expect("Failed to write texture");
+}
+
+fn write_gltf(test: &str, extra_attributes: &str) -> String {
+    let directory = directory(test);
+    write_texture(&directory);
+    std::fs::write(directory.join("scene.bin"), quad_buffer()).unwrap();
+    std::fs::write(directory.join("scene.gltf"), scene_json(Some("scene.bin"), extra_attributes)).unwrap();
+    directory.join("scene.gltf").to_string_lossy().to_string()
+}
+
+fn write_glb(test: &str) -> String {
+    let directory = directory(test);
+    write_texture(&directory);
+    let mut json = scene_json(None, r#""NORMAL": 1,"#).into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = quad_buffer();
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let mut glb = Vec::new();
+    glb.extend(b"glTF");
+    glb.extend(2u32.to_le_bytes());
+    glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(b"JSON");
+    glb.extend(json);
+    glb.extend((bin.len() as u32).to_le_bytes());
+    glb.extend(b"BIN\0");
+    glb.extend(bin);
+    std::fs::write(directory.join("scene.glb"), glb).unwrap();
+    directory.join("scene.glb").to_string_lossy().to_string()
+}
+
+fn assert_quad_scene(model: &GltfModel) {
+    assert_eq!(model.meshes.len(), 1);
+    assert_eq!(model.meshes[0].name, "quad");
+    let primitive = &model.meshes[0].primitives[0];
+    assert_eq!(primitive.mesh.vertices.len(), 4);
+    assert_eq!(primitive.mesh.indices, [0, 1, 2, 0, 2, 3]);
+    assert_eq!(primitive.mesh.vertices[2].position, [0.5, 0.5, 0.0]);
+    assert_eq!(primitive.mesh.vertices[2].normal, [0.0, 0.0, 1.0]);
+    assert_eq!(primitive.mesh.vertices[2].uv, [1.0, 0.0]);
+
+    assert_eq!(primitive.material.color, Color::new(1.0, 1.0, 1.0, 0.5));
+    assert_eq!(primitive.material.shading, Shading::Pbr { metallic: 0.25, roughness: 0.5 });
+    let image = primitive.material.image.as_ref().expect("Base color texture was not loaded");
+    assert_eq!(image.image.to_rgba8().get_pixel(0, 0).0, [0, 255, 0, 255]);
+
+    assert_eq!(model.roots, [0, 2, 3]);
+    let parent = &model.nodes[0];
+    assert_eq!(parent.name, "parent");
+    assert_eq!(parent.children, [1]);
+    assert_eq!(parent.transform.position, Vector3::new(1.0, 0.0, 0.0));
+    let expected = Quaternion::from_angle_z(Deg(90.0));
+    assert!((parent.transform.rotation - expected).magnitude() < 1e-5, "{:?}", parent.transform.rotation);
+    assert_eq!(model.nodes[1].mesh, Some(0));
+    assert_eq!(model.nodes[1].transform.scale, Vector3::new(2.0, 2.0, 2.0));
+}
+
+#[test]
+fn gltf_files_load_meshes_nodes_and_materials() {
+    let model = GltfModel::from_file(&write_gltf("gltf", r#""NORMAL": 1,"#)).unwrap();
+    assert_quad_scene(&model);
+}
+
+#[test]
+fn glb_files_load_the_same_scene() {
+    let model = GltfModel::from_file(&write_glb("glb")).unwrap();
+    assert_quad_scene(&model);
+}
+
+#[test]
+fn cameras_are_placed_by_their_nodes() {
+    let model = GltfModel::from_file(&write_gltf("cameras", r#""NORMAL": 1,"#)).unwrap();
+    assert_eq!(model.cameras.len(), 2);
+
+    let camera = model.cameras.iter().find(|camera| matches!(camera.projection, Projection::Perspective { .. })).unwrap();
+    assert_eq!(camera.position, Point3::new(0.0, 0.0, 5.0));
+    assert!((camera.target - Point3::new(0.0, 0.0, 4.0)).magnitude() < 1e-5);
+    let Projection::Perspective { fov_y, near, far, aspect, .. } = camera.projection else { unreachable!() };
+    assert!((fov_y.0 - Deg::from(Rad(0.8f32)).0).abs() < 1e-4);
+    assert_eq!((near, far, aspect), (0.1, 50.0, 1.5));
+
+    // The top camera is turned to look straight down
+    let camera = model.cameras.iter().find(|camera| matches!(camera.projection, Projection::Orthographic { .. })).unwrap();
+    assert!((camera.target - Point3::new(0.0, 9.0, 0.0)).magnitude() < 1e-5, "{:?}", camera.target);
+    assert!((camera.up - Vector3::new(0.0, 0.0, -1.0)).magnitude() < 1e-5, "{:?}", camera.up);
+    let Projection::Orthographic { left, right, bottom, top, aspect, .. } = camera.projection else { unreachable!() };
+    assert_eq!((left, right, bottom, top, aspect), (-4.0, 4.0, -2.0, 2.0, 2.0));
+}
+
+#[test]
+fn primitives_without_normals_are_flat_shaded() {
+    let model = GltfModel::from_file(&write_gltf("flat", "")).unwrap();
+    let mesh = &model.meshes[0].primitives[0].mesh;
+    assert_eq!(mesh.vertices.len(), 6);
+    assert!(mesh.vertices.iter().all(|v| v.normal == [0.0, 0.0, 1.0]));
+}
+
+#[test]
+fn scenes_are_drawn_through_their_node_hierarchy() {
+    let Some(mut renderer) = headless_renderer(64, 64) else { return };
+    let model = GltfModel::from_file(&write_gltf("draw", r#""NORMAL": 1,"#)).unwrap();
+    let mut camera = Camera::default_perspective(64.0, 64.0);
+    camera.update_viewport(64.0, 64.0);
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_gltf(&mut camera, Transform::new(), &model);
+    });
+
+    // The quad is scaled to two units and moved one unit right by its parent
+    let pixel = |x, y| frame.image.to_rgba8().get_pixel(x, y).0;
+    let [r, g, b, _] = pixel(47, 32);
+    assert!(r == 0 && b == 0 && g > 64 && g < 255, "{:?} should be half transparent green", pixel(47, 32));
+    assert_eq!(pixel(24, 32), [0, 0, 0, 255]);
+    assert_eq!(pixel(47, 12), [0, 0, 0, 255]);
+}
+
+#[test]
+fn unreadable_files_are_errors() {
+    assert!(matches!(GltfModel::from_file("does/not/exist.gltf"), Err(GltfError::Io { .. })));
+
+    let directory = directory("errors");
+    let path = |name: &str| directory.join(name).to_string_lossy().to_string();
+    std::fs::write(directory.join("broken.gltf"), "{ \"asset\": ").unwrap();
+    assert!(matches!(GltfModel::from_file(&path("broken.gltf")), Err(GltfError::Invalid { .. })));
+
+    std::fs::write(directory.join("missing_buffer.gltf"), scene_json(Some("missing.bin"), "")).unwrap();
+    assert!(matches!(GltfModel::from_file(&path("missing_buffer.gltf")), Err(GltfError::Io { .. })));
+
+    std::fs::write(directory.join("remote.gltf"), scene_json(Some("https://example.com/scene.bin"), "")).unwrap();
+    let error = GltfModel::from_file(&path("remote.gltf")).unwrap_err();
+    assert!(matches!(error, GltfError::Invalid { .. }), "{}", error);
+}
+
+#[test]
+fn attribute_counts_must_match_the_positions() {
+    let directory = directory("counts");
+    write_texture(&directory);
+    std::fs::write(directory.join("scene.bin"), quad_buffer()).unwrap();
+    let json = scene_json(Some("scene.bin"), r#""NORMAL": 1,"#);
+    for (name, accessor) in [("normals", r#""byteOffset": 48, "componentType": 5126, "count": "#), ("uvs", r#""byteOffset": 96, "componentType": 5126, "count": "#)] {
+        for count in [3, 5] {
+            let path = directory.join(format!("{}_{}.gltf", name, count));
+            std::fs::write(&path, json.replace(&format!("{}4", accessor), &format!("{}{}", accessor, count))).unwrap();
+            let error = GltfModel::from_file(&path.to_string_lossy()).unwrap_err();
+            assert!(matches!(error, GltfError::Invalid { .. }), "{} {}: {}", name, count, error);
+        }
+    }
+}
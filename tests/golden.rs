@@ -0,0 +1,470 @@
+use rgfx::prelude::*;
+use rgfx::testing::{headless_renderer, render_frame, GoldenImage, UPDATE_SNAPSHOTS_ENV};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+
+fn camera() -> Camera {
+    let mut camera = Camera::default_orthographic(WIDTH as f32, HEIGHT as f32);
+    camera.update_viewport(WIDTH as f32, HEIGHT as f32);
+    camera
+}
+
+fn golden(name: &str) -> GoldenImage {
+    // Allow a few edge pixels to differ between rasterizers
+    GoldenImage::new(name)
+        .with_tolerance(8)
+        .with_max_mismatched_pixels(16)
+}
+
+fn checkerboard() -> Image {
+    let img = image::RgbaImage::from_fn(4, 4, |x, y| {
+        if (x + y) % 2 == 0 { image::Rgba([255, 255, 255, 255]) } else { image::Rgba([0, 0, 0, 255]) }
+    });
+    Image {
+        path: "golden_checkerboard".to_string(),
+        image: image::DynamicImage::ImageRgba8(img)
+    }
+}
+
+#[test]
+fn clear_color() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::ORANGE);
+    });
+    golden("clear_color").assert(&frame);
+}
+
+#[test]
+fn triangle() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let transform = Transform::new().with_scale(0.75, 0.75, 0.75);
+        canvas.draw_triangle(&mut camera(), transform, DrawStyle::new(Color::BLUE));
+    });
+    golden("triangle").assert(&frame);
+}
+
+#[test]
+fn rotated_rectangle() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let transform = Transform::new()
+            .with_position(0.2, 0.1, 0.0)
+            .with_rotation(0.0, 0.0, 30.0);
+        canvas.draw_rectangle(&mut camera(), transform, 1.0, 0.5, DrawStyle::new(Color::RED));
+    });
+    golden("rotated_rectangle").assert(&frame);
+}
+
+#[test]
+fn circle() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let transform = Transform::new().with_position(-0.2, 0.0, 0.0);
+        canvas.draw_circle(&mut camera(), transform, 0.6, DrawStyle::new(Color::GREEN));
+    });
+    golden("circle").assert(&frame);
+}
+
+#[test]
+fn textured_rectangle() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::GRAY);
+        let style = DrawStyle::new(Color::WHITE).with_image(checkerboard());
+        canvas.draw_rectangle(&mut camera(), Transform::new(), 1.5, 1.5, style);
+    });
+    golden("textured_rectangle").assert(&frame);
+}
+
+#[test]
+fn mismatch_writes_diff() {
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() { return; }
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let dir = std::env::temp_dir().join("rgfx_golden_mismatch");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let white = render_frame(&mut renderer, |canvas| canvas.clear(Color::WHITE));
+    let black = render_frame(&mut renderer, |canvas| canvas.clear(Color::BLACK));
+
+    let golden = GoldenImage::new("mismatch").with_dir(&dir);
+    assert!(golden.compare(&white).is_err());
+    assert!(!golden.reference_path().exists());
+
+    golden.update(&white).expect("Failed to write reference");
+    assert!(golden.compare(&white).is_ok());
+    assert!(golden.compare(&black).is_err());
+    assert!(golden.diff_path().exists());
+    assert!(golden.actual_path().exists());
+}
+
+#[test]
+fn instanced_quads() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let colors = [Color::RED, Color::GREEN, Color::BLUE, Color::YELLOW];
+        let instances: Vec<InstanceData> = (0..16)
+            .map(|i| {
+                let transform = Transform::new()
+                    .with_position((i % 4) as f32 * 0.5 - 0.75, (i / 4) as f32 * 0.5 - 0.75, 0.0)
+                    .with_rotation(0.0, 0.0, i as f32 * 10.0);
+                InstanceData::new(transform)
+                    .with_tint(colors[i % 4])
+                    .with_uv_rect(0.0, 0.0, 0.5, 0.5)
+            })
+            .collect();
+        let mesh = MeshBuilder2D::from_rectangle(0.35, 0.35, Color::WHITE.into());
+        let style = DrawStyle::default().with_image(checkerboard());
+        canvas.draw_instanced(&mut camera(), mesh, &instances, style);
+    });
+    golden("instanced_quads").assert(&frame);
+}
+
+#[test]
+fn blend_modes() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let modes = [
+        BlendMode::None,
+        BlendMode::Alpha,
+        BlendMode::PremultipliedAlpha,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ];
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::GRAY);
+        let mut camera = camera();
+        canvas.draw_rectangle(&mut camera, Transform::new(), 2.0, 0.6, DrawStyle::new(Color::CYAN));
+        for (i, mode) in modes.iter().enumerate() {
+            let transform = Transform::new().with_position(i as f32 * 0.3 - 0.75, 0.0, 0.0);
+            let style = DrawStyle::new(Color::new(1.0, 0.2, 0.2, 0.5)).with_blend_mode(*mode);
+            canvas.draw_rectangle(&mut camera, transform, 0.25, 1.5, style);
+        }
+    });
+    golden("blend_modes").assert(&frame);
+}
+
+#[test]
+fn text() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let font = Font::from_file("examples/assets/DejaVuSans.ttf").expect("Failed to load font");
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+        let transform = Transform::new().with_position(0.0, 0.6, 0.0);
+        let style = DrawStyle::new(Color::BLACK).with_text_align(TextAlign::Center);
+        canvas.draw_text(&mut camera, transform, "AVo\nrgfx", &font, 0.5, style);
+    });
+    golden("text").assert(&frame);
+}
+
+#[test]
+fn strokes() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+        let zigzag = [Point2::new(-0.8, 0.3), Point2::new(-0.5, 0.8), Point2::new(-0.2, 0.3), Point2::new(0.1, 0.8)];
+        let joins = [LineJoin::Miter, LineJoin::Round, LineJoin::Bevel];
+        for (i, join) in joins.iter().enumerate() {
+            let transform = Transform::new().with_position(0.0, i as f32 * -0.55, 0.0);
+            let stroke = Stroke::new(0.12).with_join(*join).with_cap(LineCap::Round);
+            canvas.draw_polyline(&mut camera, transform, &zigzag, false, DrawStyle::new(Color::BLUE).with_stroke(stroke));
+        }
+        let outline = DrawStyle::new(Color::RED).with_stroke(Stroke::new(0.08));
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.55, 0.5, 0.0), 0.5, 0.5, outline.clone());
+        canvas.draw_circle(&mut camera, Transform::new().with_position(0.55, -0.4, 0.0), 0.3, outline);
+        let square = DrawStyle::new(Color::BLACK).with_stroke(Stroke::new(0.06).with_cap(LineCap::Square));
+        canvas.draw_line(&mut camera, Transform::new(), Point2::new(-0.8, -0.85), Point2::new(0.8, -0.85), square);
+    });
+    golden("strokes").assert(&frame);
+}
+
+#[test]
+fn paths() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+        let heart = Path::new()
+            .move_to(Point2::new(0.0, -0.5))
+            .cubic_to(Point2::new(-0.9, 0.1), Point2::new(-0.4, 0.7), Point2::new(0.0, 0.3))
+            .cubic_to(Point2::new(0.4, 0.7), Point2::new(0.9, 0.1), Point2::new(0.0, -0.5))
+            .close();
+        let transform = Transform::new().with_position(-0.45, 0.4, 0.0);
+        canvas.draw_mesh(&mut camera, transform, heart.fill(FillRule::NonZero, Color::RED.into()), DrawStyle::default());
+        canvas.draw_mesh(&mut camera, transform, heart.stroke(&Stroke::new(0.06), Color::BLACK.into()), DrawStyle::default());
+
+        let star = (1..5).fold(Path::new().move_to(Point2::new(0.0, 0.45)), |path, i| {
+            let angle = std::f32::consts::FRAC_PI_2 + i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+            path.line_to(Point2::new(angle.cos() * 0.45, angle.sin() * 0.45))
+        }).close();
+        let transform = Transform::new().with_position(0.5, 0.45, 0.0);
+        canvas.draw_mesh(&mut camera, transform, star.fill(FillRule::EvenOdd, Color::BLUE.into()), DrawStyle::default());
+
+        let wave = Path::new()
+            .move_to(Point2::new(-0.9, -0.6))
+            .quad_to(Point2::new(-0.45, -0.1), Point2::new(0.0, -0.6))
+            .quad_to(Point2::new(0.45, -1.1), Point2::new(0.9, -0.6))
+            .arc_to(Point2::new(0.9, -0.2), Point2::new(0.5, -0.2), 0.2);
+        let stroke = Stroke::new(0.08).with_cap(LineCap::Round).with_join(LineJoin::Round);
+        canvas.draw_mesh(&mut camera, Transform::new(), wave.stroke(&stroke, Color::GREEN.into()), DrawStyle::default());
+    });
+    golden("paths").assert(&frame);
+}
+
+#[test]
+fn polygon() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::GRAY);
+        let star: Vec<Point2<f32>> = (0..10)
+            .map(|i| {
+                let angle = i as f32 * std::f32::consts::PI / 5.0;
+                let radius = if i % 2 == 0 { 0.9 } else { 0.4 };
+                Point2::new(angle.cos() * radius, angle.sin() * radius)
+            })
+            .collect();
+        let hole = vec![Point2::new(-0.15, -0.15), Point2::new(0.15, -0.15), Point2::new(0.15, 0.15), Point2::new(-0.15, 0.15)];
+        let mesh = MeshBuilder2D::from_polygon(&star, &[hole], Color::WHITE.into());
+        canvas.draw_mesh(&mut camera(), Transform::new(), mesh, DrawStyle::default().with_image(checkerboard()));
+    });
+    golden("polygon").assert(&frame);
+}
+
+#[test]
+fn curved_shapes() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+        let at = |x: f32, y: f32| Transform::new().with_position(x, y, 0.0);
+        canvas.draw_rounded_rectangle(&mut camera, at(-0.5, 0.5), 0.8, 0.6, [0.3, 0.05, 0.3, 0.0], DrawStyle::new(Color::BLUE));
+        canvas.draw_ellipse(&mut camera, at(0.5, 0.5), 0.4, 0.25, DrawStyle::new(Color::GREEN));
+        canvas.draw_ring(&mut camera, at(-0.5, -0.5), 0.2, 0.35, DrawStyle::new(Color::RED));
+        canvas.draw_pie(&mut camera, at(0.5, -0.5), 0.35, 30.0, 300.0, DrawStyle::new(Color::ORANGE));
+        let stroke = Stroke::new(0.08).with_cap(LineCap::Round);
+        canvas.draw_arc(&mut camera, at(0.0, 0.0), 0.25, 200.0, 340.0, DrawStyle::new(Color::BLACK).with_stroke(stroke));
+    });
+    golden("curved_shapes").assert(&frame);
+}
+
+#[test]
+fn screen_space_overlay() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let font = Font::from_file("examples/assets/DejaVuSans.ttf").expect("Failed to load font");
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_circle(&mut camera(), Transform::new(), 0.8, DrawStyle::new(Color::BLUE));
+
+        let mut screen = canvas.screen_camera();
+        canvas.draw_rectangle(&mut screen, Transform::new().with_position(32.0, 6.0, 0.0), 64.0, 12.0, DrawStyle::new(Color::GRAY));
+        canvas.draw_text(&mut screen, Transform::new().with_position(2.0, 0.0, 0.0), "HUD", &font, 12.0, DrawStyle::new(Color::WHITE));
+        let outline = DrawStyle::new(Color::YELLOW).with_stroke(Stroke::new(2.0));
+        canvas.draw_polyline(&mut screen, Transform::new(), &[Point2::new(4.0, 60.0), Point2::new(20.0, 44.0), Point2::new(36.0, 60.0)], false, outline);
+    });
+    golden("screen_space_overlay").assert(&frame);
+}
+
+#[test]
+fn transform_stack() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+
+        // A three-segment arm, each segment rotating relative to its parent
+        canvas.push_transform(Transform::new().with_position(-0.6, -0.6, 0.0));
+        for (angle, color) in [(60.0, Color::RED), (-45.0, Color::GREEN), (-60.0, Color::BLUE)] {
+            canvas.push_transform(Transform::new().with_rotation(0.0, 0.0, angle));
+            canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.25, 0.0, 0.0), 0.5, 0.12, DrawStyle::new(color));
+            canvas.draw_circle(&mut camera, Transform::new(), 0.09, DrawStyle::new(Color::BLACK));
+            canvas.push_transform(Transform::new().with_position(0.5, 0.0, 0.0));
+        }
+        for _ in 0..7 {
+            canvas.pop_transform();
+        }
+    });
+    golden("transform_stack").assert(&frame);
+}
+
+#[test]
+fn clip_rects() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let font = Font::from_file("examples/assets/DejaVuSans.ttf").expect("Failed to load font");
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+        canvas.push_clip_rect(8.0, 8.0, 48.0, 24.0);
+        canvas.draw_circle(&mut camera, Transform::new().with_position(0.0, 0.5, 0.0), 0.6, DrawStyle::new(Color::BLUE));
+        canvas.push_world_clip_rect(&mut camera, Transform::new().with_position(-0.25, 0.5, 0.0).with_rotation(0.0, 0.0, 20.0), 0.5, 0.5);
+        canvas.draw_text(&mut camera, Transform::new().with_position(-0.6, 0.9, 0.0), "clip", &font, 0.6, DrawStyle::new(Color::WHITE));
+        canvas.pop_clip_rect();
+        canvas.pop_clip_rect();
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.0, -0.5, 0.0), 1.5, 0.5, DrawStyle::new(Color::RED));
+    });
+    golden("clip_rects").assert(&frame);
+}
+
+#[test]
+fn masks() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+        let star = (1..5).fold(Path::new().move_to(Point2::new(0.0, 0.9)), |path, i| {
+            let angle = std::f32::consts::FRAC_PI_2 + i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+            path.line_to(Point2::new(angle.cos() * 0.9, angle.sin() * 0.9))
+        }).close();
+        canvas.push_mask(&mut camera, Transform::new(), star.fill(FillRule::NonZero, Color::WHITE.into()), MaskMode::Inside);
+        for i in 0..8 {
+            let style = DrawStyle::new(if i % 2 == 0 { Color::RED } else { Color::BLUE });
+            canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.0, i as f32 * 0.25 - 0.875, 0.0), 2.0, 0.25, style);
+        }
+        canvas.push_mask(&mut camera, Transform::new(), MeshBuilder2D::from_circle(0.2, 32, Color::WHITE.into()), MaskMode::Outside);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.6, 0.6, DrawStyle::new(Color::BLACK));
+        canvas.pop_mask();
+        canvas.pop_mask();
+    });
+    golden("masks").assert(&frame);
+}
+
+#[test]
+fn render_target() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let target = RenderTarget::new(32, 32);
+    let mut target_canvas = target.canvas();
+    target_canvas.clear(Color::BLUE);
+    target_canvas.draw_circle(&mut camera(), Transform::new(), 0.8, DrawStyle::new(Color::YELLOW));
+    target_canvas.draw_rectangle(&mut camera(), Transform::new(), 0.5, 0.5, DrawStyle::default().with_image(checkerboard()));
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        canvas.render_to_target(&target, target_canvas);
+        let mut camera = camera();
+        let style = DrawStyle::default().with_image(target.image());
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(-0.4, 0.4, 0.0), 1.0, 1.0, style.clone());
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.4, -0.4, 0.0).with_rotation(0.0, 0.0, 30.0), 0.8, 0.8, style);
+    });
+    golden("render_target").assert(&frame);
+}
+
+fn post_effect_scene(canvas: &mut Canvas) {
+    let mut camera = camera();
+    canvas.clear(Color::new(0.1, 0.1, 0.2, 1.0));
+    canvas.draw_circle(&mut camera, Transform::new().with_position(-0.4, 0.3, 0.0), 0.3, DrawStyle::new(Color::WHITE));
+    canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.4, 0.3, 0.0), 0.6, 0.6, DrawStyle::new(Color::ORANGE));
+    canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.0, -0.5, 0.0), 1.6, 0.6, DrawStyle::default().with_image(checkerboard()));
+}
+
+#[test]
+fn post_effects() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let effects = [
+        ("post_blur", PostEffect::blur(3.0)),
+        ("post_bloom", PostEffect::bloom(0.5, 2.0, 4.0)),
+        ("post_vignette", PostEffect::vignette(0.8, 0.5)),
+        ("post_crt", PostEffect::crt(0.5, 0.1)),
+        ("post_grayscale", PostEffect::grayscale(1.0)),
+    ];
+
+    for (name, effect) in effects {
+        let frame = render_frame(&mut renderer, |canvas| {
+            canvas.add_post_effect(effect);
+            post_effect_scene(canvas);
+        });
+        golden(name).assert(&frame);
+    }
+}
+
+#[test]
+fn material() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let stripes = Material::new("
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            let stripe = step(0.5, fract((in.uv.x + in.uv.y) * material.params[0].x));
+            let texel = textureSample(texture_0, sampler_0, in.uv);
+            return mix(base_color(in), texel, stripe);
+        }
+    ").with_params(&[4.0]).with_texture(checkerboard());
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::WHITE);
+        let mut camera = camera();
+        canvas.draw_circle(&mut camera, Transform::new().with_position(-0.4, 0.4, 0.0), 0.5, DrawStyle::new(Color::RED).with_material(stripes.clone()));
+        let rotated = Transform::new().with_position(0.4, -0.4, 0.0).with_rotation(0.0, 0.0, 30.0);
+        canvas.draw_rectangle(&mut camera, rotated, 0.9, 0.9, DrawStyle::new(Color::BLUE).with_material(stripes));
+    });
+    golden("material").assert(&frame);
+}
+
+#[test]
+fn intersecting_meshes_3d() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let quad = Mesh {
+        vertices: vec![
+            Vertex3D { position: [-1.0, -1.0, 0.0], normal: [0.0, 0.0, 1.0], uv: [0.0, 1.0] },
+            Vertex3D { position: [1.0, -1.0, 0.0], normal: [0.0, 0.0, 1.0], uv: [1.0, 1.0] },
+            Vertex3D { position: [1.0, 1.0, 0.0], normal: [0.0, 0.0, 1.0], uv: [1.0, 0.0] },
+            Vertex3D { position: [-1.0, 1.0, 0.0], normal: [0.0, 0.0, 1.0], uv: [0.0, 0.0] },
+        ],
+        indices: vec![0, 1, 2, 0, 2, 3],
+    };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let mut camera = Camera::default_perspective(WIDTH as f32, HEIGHT as f32);
+        camera.update_viewport(WIDTH as f32, HEIGHT as f32);
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_rotation(0.0, 40.0, 0.0), quad.clone(), Material3D::new(Color::RED));
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_rotation(0.0, -40.0, 0.0), quad.clone(), Material3D::new(Color::BLUE));
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_rotation(60.0, 0.0, 0.0), quad, Material3D::default().with_image(checkerboard()));
+    });
+    golden("intersecting_meshes_3d").assert(&frame);
+}
+
+#[test]
+fn primitives_3d() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::GRAY);
+        let mut camera = Camera::default_perspective(WIDTH as f32, HEIGHT as f32);
+        camera.position = Point3::new(0.0, 3.0, 6.0);
+        camera.update_viewport(WIDTH as f32, HEIGHT as f32);
+        let material = Material3D::default().with_image(checkerboard());
+        let tilted = |x: f32, y: f32| Transform::new().with_position(x, y, 0.0).with_rotation(20.0, 30.0, 0.0);
+        canvas.draw_mesh_3d(&mut camera, tilted(-1.2, 1.2), MeshBuilder3D::from_cube(1.0, 1.0, 1.0), material.clone());
+        canvas.draw_mesh_3d(&mut camera, tilted(1.2, 1.2), MeshBuilder3D::from_uv_sphere(0.6, 16, 8), material.clone());
+        canvas.draw_mesh_3d(&mut camera, tilted(-1.2, -0.6), MeshBuilder3D::from_cone(0.6, 1.2, 16), material.clone());
+        canvas.draw_mesh_3d(&mut camera, tilted(1.2, -0.6), MeshBuilder3D::from_torus(0.5, 0.2, 24, 12), material);
+    });
+    golden("primitives_3d").assert(&frame);
+}
+
+#[test]
+fn lighting_3d() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let mut camera = Camera::default_perspective(WIDTH as f32, HEIGHT as f32);
+        camera.position = Point3::new(0.0, 2.0, 6.0);
+        camera.update_viewport(WIDTH as f32, HEIGHT as f32);
+        canvas.set_lighting(Lighting::new()
+            .with_ambient(Color::new(0.05, 0.05, 0.1, 1.0))
+            .with_light(Light::directional(Vector3::new(-1.0, -1.0, -1.0), Color::new(1.0, 0.9, 0.8, 1.0)))
+            .with_light(Light::point(Point3::new(0.0, 0.5, 1.5), 3.0, Color::new(0.2, 0.4, 1.0, 1.0)).with_intensity(2.0))
+            .with_light(Light::spot(Point3::new(0.0, 4.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 8.0, 30.0, Color::WHITE)));
+
+        let sphere = || MeshBuilder3D::from_uv_sphere(0.6, 24, 12);
+        let at = |x: f32| Transform::new().with_position(x, 0.0, 0.0);
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_position(0.0, -0.6, 0.0), MeshBuilder3D::from_plane(6.0, 4.0, 1, 1), Material3D::new(Color::GRAY));
+        canvas.draw_mesh_3d(&mut camera, at(-1.5), sphere(), Material3D::new(Color::RED));
+        canvas.draw_mesh_3d(&mut camera, at(0.0), sphere(), Material3D::new(Color::WHITE).with_shading(Shading::Pbr { metallic: 1.0, roughness: 0.3 }));
+        canvas.draw_mesh_3d(&mut camera, at(1.5), sphere(), Material3D::new(Color::GREEN).with_shading(Shading::Pbr { metallic: 0.0, roughness: 0.6 }));
+    });
+    golden("lighting_3d").assert(&frame);
+}
@@ -0,0 +1,137 @@
+use rgfx::prelude::*;
+use rgfx::testing::{headless_renderer, render_frame};
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const WIDTH: u32 = 16;
+const HEIGHT: u32 = 16;
+
+fn solid_shader(color: &str) -> String {
+    format!("
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {{
+            return vec4<f32>({});
+        }}
+    ", color)
+}
+
+/// Writes the shader to a file unique to the test, moving its modification time forward so the change is always seen.
+fn write_shader(name: &str, source: &str, generation: u64) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rgfx_hot_reload_{}_{}.wgsl", name, std::process::id()));
+    std::fs::write(&path, source).expect("Failed to write shader");
+    File::options().write(true).open(&path).expect("Failed to open shader")
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + generation))
+        .expect("Failed to set modification time");
+    path
+}
+
+fn draw_square(canvas: &mut Canvas, material: &Material) {
+    canvas.clear(Color::BLACK);
+    let transform = Transform::new().with_position(8.0, 8.0, 0.0);
+    canvas.draw_rectangle(&mut canvas.screen_camera(), transform, 16.0, 16.0, DrawStyle::default().with_material(material.clone()));
+}
+
+fn center_pixel(frame: &Image) -> [u8; 4] {
+    frame.image.to_rgba8().get_pixel(WIDTH / 2, HEIGHT / 2).0
+}
+
+#[test]
+fn changed_materials_are_reloaded() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    renderer.set_shader_hot_reload(true);
+    let path = write_shader("material", &solid_shader("1.0, 0.0, 0.0, 1.0"), 0);
+    let material = Material::from_file(path.to_str().unwrap()).expect("Failed to load material");
+
+    let frame = render_frame(&mut renderer, |canvas| draw_square(canvas, &material));
+    assert_eq!(center_pixel(&frame), [255, 0, 0, 255]);
+
+    write_shader("material", &solid_shader("0.0, 0.0, 1.0, 1.0"), 1);
+    let frame = render_frame(&mut renderer, |canvas| draw_square(canvas, &material));
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+
+    // A broken shader keeps the last working version
+    write_shader("material", "@fragment fn fs_main( -> broken", 2);
+    let frame = render_frame(&mut renderer, |canvas| draw_square(canvas, &material));
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+
+    write_shader("material", &solid_shader("0.0, 1.0, 0.0, 1.0"), 3);
+    let frame = render_frame(&mut renderer, |canvas| draw_square(canvas, &material));
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn changed_post_effects_are_reloaded() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    renderer.set_shader_hot_reload(true);
+    let path = write_shader("post", &solid_shader("1.0, 0.0, 0.0, 1.0"), 0);
+    let effect = PostEffect::from_file(path.to_str().unwrap()).expect("Failed to load post effect");
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.add_post_effect(effect.clone());
+    });
+    assert_eq!(center_pixel(&frame), [255, 0, 0, 255]);
+
+    write_shader("post", &solid_shader("0.0, 1.0, 0.0, 1.0"), 1);
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.add_post_effect(effect.clone());
+    });
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn shaders_are_not_reloaded_unless_enabled() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let path = write_shader("disabled", &solid_shader("1.0, 0.0, 0.0, 1.0"), 0);
+    let material = Material::from_file(path.to_str().unwrap()).expect("Failed to load material");
+
+    render_frame(&mut renderer, |canvas| draw_square(canvas, &material));
+    write_shader("disabled", &solid_shader("0.0, 0.0, 1.0, 1.0"), 1);
+    let frame = render_frame(&mut renderer, |canvas| draw_square(canvas, &material));
+    assert_eq!(center_pixel(&frame), [255, 0, 0, 255]);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn edited_files_loaded_again_are_recompiled() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let path = write_shader("reloaded", &solid_shader("1.0, 0.0, 0.0, 1.0"), 0);
+    let old_material = Material::from_file(path.to_str().unwrap()).expect("Failed to load material");
+    let old_effect = PostEffect::from_file(path.to_str().unwrap()).expect("Failed to load post effect");
+    render_frame(&mut renderer, |canvas| {
+        draw_square(canvas, &old_material);
+        canvas.add_post_effect(old_effect.clone());
+    });
+
+    write_shader("reloaded", &solid_shader("0.0, 0.0, 1.0, 1.0"), 1);
+    let material = Material::from_file(path.to_str().unwrap()).expect("Failed to load material");
+    let effect = PostEffect::from_file(path.to_str().unwrap()).expect("Failed to load post effect");
+    let frame = render_frame(&mut renderer, |canvas| draw_square(canvas, &material));
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.add_post_effect(effect.clone());
+    });
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+
+    // Without hot reload, what was loaded before keeps drawing the old shader
+    let frame = render_frame(&mut renderer, |canvas| draw_square(canvas, &old_material));
+    assert_eq!(center_pixel(&frame), [255, 0, 0, 255]);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn missing_shader_files_are_reported() {
+    let error = Material::from_file("does/not/exist.wgsl").unwrap_err();
+    assert!(matches!(&error, ShaderError::Io { path, .. } if path == "does/not/exist.wgsl"));
+    assert!(error.to_string().starts_with("Failed to read shader file does/not/exist.wgsl"));
+    assert!(matches!(PostEffect::from_file("does/not/exist.wgsl"), Err(ShaderError::Io { .. })));
+}
@@ -0,0 +1,151 @@
+use rgfx::prelude::*;
+use rgfx::testing::{headless_renderer, render_frame};
+use cgmath::{Point3, Vector3};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+
+fn camera() -> Camera {
+    let mut camera = Camera::default_perspective(WIDTH as f32, HEIGHT as f32);
+    camera.update_viewport(WIDTH as f32, HEIGHT as f32);
+    camera
+}
+
+/// A square facing +z towards the camera, covering the whole frame.
+fn quad() -> Mesh<Vertex3D> {
+    let vertex = |x: f32, y: f32| Vertex3D { position: [x, y, 0.0], normal: [0.0, 0.0, 1.0], uv: [0.0, 0.0] };
+    Mesh {
+        vertices: vec![vertex(-3.0, -3.0), vertex(3.0, -3.0), vertex(3.0, 3.0), vertex(-3.0, 3.0)],
+        indices: vec![0, 1, 2, 0, 2, 3],
+    }
+}
+
+fn matte(color: Color) -> Material3D {
+    Material3D::new(color).with_shading(Shading::BlinnPhong { specular: 0.0, shininess: 1.0 })
+}
+
+/// Draws the quad under the lighting, against a blue background.
+fn render_lit(lighting: Lighting, material: Material3D) -> Option<Image> {
+    let mut renderer = headless_renderer(WIDTH, HEIGHT)?;
+    let mut camera = camera();
+    Some(render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLUE);
+        canvas.set_lighting(lighting);
+        canvas.draw_mesh_3d(&mut camera, Transform::new(), quad(), material);
+    }))
+}
+
+fn pixel(frame: &Image, x: u32, y: u32) -> [u8; 4] {
+    frame.image.to_rgba8().get_pixel(x, y).0
+}
+
+fn no_ambient() -> Lighting {
+    Lighting::new().with_ambient(Color::BLACK)
+}
+
+#[test]
+fn meshes_are_unlit_without_lighting() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.set_lighting(no_ambient());
+        canvas.clear(Color::BLACK);
+        assert_eq!(canvas.lighting(), Some(&no_ambient()));
+        canvas.clear_lighting();
+        assert!(canvas.lighting().is_none());
+        canvas.draw_mesh_3d(&mut camera, Transform::new(), quad(), Material3D::new(Color::RED));
+    });
+
+    assert_eq!(pixel(&frame, 32, 32), [255, 0, 0, 255]);
+}
+
+#[test]
+fn directional_lights_only_light_surfaces_facing_them() {
+    let facing = no_ambient().with_light(Light::directional(Vector3::new(0.0, 0.0, -1.0), Color::WHITE));
+    let Some(frame) = render_lit(facing, matte(Color::RED)) else { return };
+    assert_eq!(pixel(&frame, 32, 32), [255, 0, 0, 255]);
+
+    let behind = no_ambient().with_light(Light::directional(Vector3::new(0.0, 0.0, 1.0), Color::WHITE));
+    let Some(frame) = render_lit(behind, matte(Color::RED)) else { return };
+    assert_eq!(pixel(&frame, 32, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+fn ambient_light_reaches_every_surface() {
+    let lighting = Lighting::new()
+        .with_ambient(Color::new(0.5, 0.5, 0.5, 1.0))
+        .with_light(Light::directional(Vector3::new(0.0, 0.0, 1.0), Color::WHITE));
+    let Some(frame) = render_lit(lighting, matte(Color::WHITE)) else { return };
+
+    // Half of the linear light, encoded into sRGB
+    let [r, g, b, _] = pixel(&frame, 32, 32);
+    assert!((180..=195).contains(&r) && r == g && g == b, "{:?}", [r, g, b]);
+}
+
+#[test]
+fn point_lights_fade_out_at_their_range() {
+    let lighting = no_ambient().with_light(Light::point(Point3::new(0.0, 0.0, 1.0), 2.0, Color::WHITE));
+    let Some(frame) = render_lit(lighting, matte(Color::WHITE)) else { return };
+
+    let center = pixel(&frame, 32, 32);
+    assert!(center[0] > 100, "{:?}", center);
+    assert_eq!(pixel(&frame, 1, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+fn spot_lights_only_light_their_cone() {
+    let lighting = no_ambient().with_light(Light::spot(Point3::new(0.0, 0.0, 2.0), Vector3::new(0.0, 0.0, -1.0), 10.0, 20.0, Color::WHITE));
+    let Some(frame) = render_lit(lighting, matte(Color::WHITE)) else { return };
+
+    let center = pixel(&frame, 32, 32);
+    assert!(center[0] > 200, "{:?}", center);
+    assert_eq!(pixel(&frame, 8, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+fn blinn_phong_highlights_face_the_camera() {
+    let lighting = no_ambient().with_light(Light::directional(Vector3::new(0.0, 0.0, -1.0), Color::WHITE));
+    let material = Material3D::new(Color::BLACK).with_shading(Shading::BlinnPhong { specular: 1.0, shininess: 128.0 });
+    let Some(frame) = render_lit(lighting, material) else { return };
+
+    let center = pixel(&frame, 32, 32);
+    let edge = pixel(&frame, 2, 32);
+    assert_eq!(center, [255, 255, 255, 255]);
+    assert!(edge[0] < 128, "edge {:?}", edge);
+}
+
+#[test]
+fn light_color_and_intensity_tint_the_surface() {
+    let light = Light::directional(Vector3::new(0.0, 0.0, -1.0), Color::new(1.0, 0.0, 0.0, 1.0)).with_intensity(0.5);
+    let Some(frame) = render_lit(no_ambient().with_light(light), matte(Color::WHITE)) else { return };
+
+    let [r, g, b, _] = pixel(&frame, 32, 32);
+    assert!((180..=195).contains(&r) && g == 0 && b == 0, "{:?}", [r, g, b]);
+}
+
+#[test]
+fn unlit_materials_ignore_the_lighting() {
+    let Some(frame) = render_lit(no_ambient(), Material3D::new(Color::RED).with_shading(Shading::Unlit)) else { return };
+    assert_eq!(pixel(&frame, 32, 32), [255, 0, 0, 255]);
+}
+
+#[test]
+fn pbr_materials_are_lit() {
+    let facing = no_ambient().with_light(Light::directional(Vector3::new(0.0, 0.0, -1.0), Color::WHITE));
+    let rough = Material3D::new(Color::RED).with_shading(Shading::Pbr { metallic: 0.0, roughness: 1.0 });
+    let Some(frame) = render_lit(facing, rough.clone()) else { return };
+    let [r, g, b, _] = pixel(&frame, 32, 32);
+    assert!(r > 240 && g < 40 && b < 40, "{:?}", [r, g, b]);
+
+    let behind = no_ambient().with_light(Light::directional(Vector3::new(0.0, 0.0, 1.0), Color::WHITE));
+    let Some(frame) = render_lit(behind, rough) else { return };
+    assert_eq!(pixel(&frame, 32, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+fn lights_beyond_the_limit_are_ignored() {
+    let lighting = (0..MAX_LIGHTS + 2).fold(Lighting::new(), |lighting, _| {
+        lighting.with_light(Light::point(Point3::new(0.0, 0.0, 1.0), 1.0, Color::WHITE))
+    });
+    assert_eq!(lighting.lights.len(), MAX_LIGHTS);
+}
@@ -0,0 +1,166 @@
+use rgfx::prelude::*;
+use cgmath::InnerSpace;
+
+fn triangles(mesh: &Mesh<Vertex3D>) -> impl Iterator<Item = [Vertex3D; 3]> + '_ {
+    mesh.indices.chunks(3).map(|t| [t[0], t[1], t[2]].map(|i| mesh.vertices[i as usize]))
+}
+
+/// Checks the invariants every generated mesh should hold, so the 3D pipeline neither culls nor mis-shades it.
+fn assert_well_formed(name: &str, mesh: &Mesh<Vertex3D>) {
+    assert!(!mesh.indices.is_empty(), "{} is empty", name);
+    assert_eq!(mesh.indices.len() % 3, 0, "{}", name);
+    assert!(mesh.indices.iter().all(|&i| (i as usize) < mesh.vertices.len()), "{} has out of range indices", name);
+
+    for vertex in &mesh.vertices {
+        let length = Vector3::from(vertex.normal).magnitude();
+        assert!((length - 1.0).abs() < 1e-4, "{} has a normal of length {}", name, length);
+    }
+
+    for [a, b, c] in triangles(mesh) {
+        let [pa, pb, pc] = [a, b, c].map(|v| Vector3::from(v.position));
+        let face = (pb - pa).cross(pc - pa);
+        let normal = Vector3::from(a.normal) + Vector3::from(b.normal) + Vector3::from(c.normal);
+        assert!(face.dot(normal) > 0.0, "{} has a triangle wound against its normals", name);
+    }
+}
+
+fn assert_uvs_within(name: &str, mesh: &Mesh<Vertex3D>) {
+    for vertex in &mesh.vertices {
+        let [u, v] = vertex.uv;
+        assert!((-1e-5..=1.0 + 1e-5).contains(&u) && (-1e-5..=1.0 + 1e-5).contains(&v), "{} has uv {:?}", name, vertex.uv);
+    }
+}
+
+#[test]
+fn primitives_are_wound_counter_clockwise_around_their_normals() {
+    let meshes = [
+        ("cube", MeshBuilder3D::from_cube(1.0, 2.0, 3.0)),
+        ("plane", MeshBuilder3D::from_plane(2.0, 1.0, 4, 3)),
+        ("uv sphere", MeshBuilder3D::from_uv_sphere(1.0, 16, 8)),
+        ("icosphere", MeshBuilder3D::from_icosphere(1.0, 2)),
+        ("cylinder", MeshBuilder3D::from_cylinder(0.5, 2.0, 12)),
+        ("cone", MeshBuilder3D::from_cone(0.5, 1.0, 12)),
+        ("capsule", MeshBuilder3D::from_capsule(0.5, 2.0, 12, 4)),
+        ("torus", MeshBuilder3D::from_torus(1.0, 0.25, 16, 8)),
+    ];
+    for (name, mesh) in &meshes {
+        assert_well_formed(name, mesh);
+        assert_uvs_within(name, mesh);
+    }
+}
+
+#[test]
+fn cube_faces_are_flat_and_fully_textured() {
+    let mesh = MeshBuilder3D::from_cube(2.0, 4.0, 6.0);
+    assert_eq!(mesh.vertices.len(), 24);
+    assert_eq!(mesh.indices.len(), 36);
+    for vertex in &mesh.vertices {
+        let [x, y, z] = vertex.position;
+        assert!((x.abs() - 1.0).abs() < 1e-5 || (y.abs() - 2.0).abs() < 1e-5 || (z.abs() - 3.0).abs() < 1e-5);
+        let offset = Vector3::from(vertex.position).dot(Vector3::from(vertex.normal));
+        assert!(offset > 0.0, "cube normal {:?} points inwards at {:?}", vertex.normal, vertex.position);
+    }
+    for [a, b, c] in triangles(&mesh) {
+        assert!(a.normal == b.normal && b.normal == c.normal);
+    }
+}
+
+#[test]
+fn planes_are_subdivided_into_a_grid() {
+    let mesh = MeshBuilder3D::from_plane(4.0, 2.0, 4, 2);
+    assert_eq!(mesh.vertices.len(), 5 * 3);
+    assert_eq!(mesh.indices.len(), 4 * 2 * 6);
+    assert!(mesh.vertices.iter().all(|v| v.position[1] == 0.0 && v.normal == [0.0, 1.0, 0.0]));
+
+    let corner = mesh.vertices.iter().find(|v| v.position == [-2.0, 0.0, -1.0]).unwrap();
+    assert_eq!(corner.uv, [0.0, 0.0]);
+    let corner = mesh.vertices.iter().find(|v| v.position == [2.0, 0.0, 1.0]).unwrap();
+    assert_eq!(corner.uv, [1.0, 1.0]);
+}
+
+#[test]
+fn sphere_normals_point_away_from_the_center() {
+    for (name, mesh) in [("uv sphere", MeshBuilder3D::from_uv_sphere(2.0, 24, 12)), ("icosphere", MeshBuilder3D::from_icosphere(2.0, 3))] {
+        for vertex in &mesh.vertices {
+            let position = Vector3::from(vertex.position);
+            assert!((position.magnitude() - 2.0).abs() < 1e-4, "{}", name);
+            assert!((position / 2.0 - Vector3::from(vertex.normal)).magnitude() < 1e-4, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn icosphere_subdivisions_split_every_triangle_in_four() {
+    // Triangles crossing the texture seam are split in two, adding a few more
+    for subdivisions in 0..4 {
+        let triangles = MeshBuilder3D::from_icosphere(1.0, subdivisions).indices.len() / 3;
+        let expected = 20 * 4usize.pow(subdivisions as u32);
+        assert!(triangles >= expected && triangles < expected + expected / 4, "{} triangles at {} subdivisions", triangles, subdivisions);
+    }
+}
+
+#[test]
+fn icosphere_vertices_are_shared_between_faces() {
+    // Only vertices on the texture seam, which grows with each level, and at the poles are duplicated
+    for subdivisions in 0..=6 {
+        let vertices = MeshBuilder3D::from_icosphere(1.0, subdivisions).vertices.len();
+        let points = 10 * 4usize.pow(subdivisions as u32) + 2;
+        assert!(vertices >= points && vertices <= points + 8 * 2usize.pow(subdivisions as u32) + 16, "{} vertices at {} subdivisions", vertices, subdivisions);
+    }
+}
+
+#[test]
+fn icosphere_triangles_do_not_stretch_across_the_seam() {
+    let mesh = MeshBuilder3D::from_icosphere(1.0, 3);
+    for triangle in triangles(&mesh) {
+        let us = triangle.map(|v| v.uv[0]);
+        let span = us.iter().cloned().fold(f32::MIN, f32::max) - us.iter().cloned().fold(f32::MAX, f32::min);
+        assert!(span < 0.5, "triangle spans {:?}", us);
+    }
+}
+
+#[test]
+fn cone_sides_slope_with_the_surface() {
+    let mesh = MeshBuilder3D::from_cone(1.0, 1.0, 16);
+    let side = mesh.vertices.iter().find(|v| v.position == [0.0, -0.5, 1.0] && v.normal[1] > 0.0).unwrap();
+    let expected = Vector3::new(0.0, 1.0, 1.0).normalize();
+    assert!((Vector3::from(side.normal) - expected).magnitude() < 1e-5);
+    assert!(mesh.vertices.iter().any(|v| v.normal == [0.0, -1.0, 0.0]), "cone has no base");
+}
+
+#[test]
+fn cylinders_and_capsules_span_their_height() {
+    let extent = |mesh: &Mesh<Vertex3D>| {
+        let ys = mesh.vertices.iter().map(|v| v.position[1]);
+        (ys.clone().fold(f32::MAX, f32::min), ys.fold(f32::MIN, f32::max))
+    };
+    let cylinder = MeshBuilder3D::from_cylinder(0.5, 3.0, 16);
+    assert_eq!(extent(&cylinder), (-1.5, 1.5));
+    assert_eq!(cylinder.vertices.iter().filter(|v| v.normal == [0.0, 1.0, 0.0]).count(), 17);
+
+    let (bottom, top) = extent(&MeshBuilder3D::from_capsule(0.5, 3.0, 16, 4));
+    assert!((bottom + 1.5).abs() < 1e-5 && (top - 1.5).abs() < 1e-5);
+
+    // A capsule shorter than its diameter is a sphere
+    let (bottom, top) = extent(&MeshBuilder3D::from_capsule(0.5, 0.2, 16, 4));
+    assert!((bottom + 0.5).abs() < 1e-5 && (top - 0.5).abs() < 1e-5);
+}
+
+#[test]
+fn torus_normals_point_away_from_the_tube_center() {
+    let mesh = MeshBuilder3D::from_torus(2.0, 0.5, 24, 12);
+    for vertex in &mesh.vertices {
+        let position = Vector3::from(vertex.position);
+        let ring = Vector3::new(position.x, 0.0, position.z).normalize() * 2.0;
+        let offset = position - ring;
+        assert!((offset.magnitude() - 0.5).abs() < 1e-4);
+        assert!((offset / 0.5 - Vector3::from(vertex.normal)).magnitude() < 1e-4);
+    }
+}
+
+#[test]
+fn meshes_too_large_for_16_bit_indices_are_empty() {
+    let mesh = MeshBuilder3D::from_uv_sphere(1.0, 1000, 1000);
+    assert!(mesh.vertices.is_empty() && mesh.indices.is_empty());
+}
+
@@ -0,0 +1,186 @@
+use rgfx::prelude::*;
+use rgfx::testing::{headless_renderer, render_frame};
+use std::path::PathBuf;
+
+/// Writes the files into a directory unique to the test and returns the path of the first one.
+fn write_files(test: &str, files: &[(&str, &str)]) -> String {
+    let directory = std::env::temp_dir().join(format!("rgfx_obj_{}_{}", test, std::process::id()));
+    std::fs::create_dir_all(&directory).expect("Failed to create directory");
+    for (name, contents) in files {
+        std::fs::write(directory.join(name), contents).expect("Failed to write file");
+    }
+    directory.join(files[0].0).to_string_lossy().to_string()
+}
+
+fn load(test: &str, obj: &str) -> Vec<ObjMesh> {
+    Mesh::<Vertex3D>::from_obj(&write_files(test, &[("model.obj", obj)])).expect("Failed to load model")
+}
+
+fn triangle_count(meshes: &[ObjMesh]) -> usize {
+    meshes.iter().map(|part| part.mesh.indices.len() / 3).sum()
+}
+
+const QUAD: &str = "
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+v -1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+";
+
+#[test]
+fn quads_and_ngons_are_split_into_triangles() {
+    let meshes = load("ngons", "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v -1 0.5 0
+        f 1 2 3 4
+        f 1 2 3 4 5
+    ");
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(triangle_count(&meshes), 2 + 3);
+}
+
+#[test]
+fn all_corner_formats_are_read() {
+    let obj = format!("{}
+        f 1 2 3
+        f 1/1 2/2 3/3
+        f 1//1 2//1 3//1
+        f 1/1/1 2/2/1 3/3/1
+        f -4/-4/-1 -3/-3/-1 -2/-2/-1
+    ", QUAD);
+    let meshes = load("corners", &obj);
+    assert_eq!(triangle_count(&meshes), 5);
+    let mesh = &meshes[0].mesh;
+    assert!(mesh.vertices.iter().all(|v| v.normal == [0.0, 0.0, 1.0]));
+
+    // Texture coordinates are flipped to start at the top of the image
+    let corner = mesh.vertices.iter().find(|v| v.position == [1.0, 1.0, 0.0] && v.uv != [0.0, 0.0]).unwrap();
+    assert_eq!(corner.uv, [1.0, 0.0]);
+
+    // Corners with the same indices share a vertex
+    let last = &mesh.indices[mesh.indices.len() - 6..];
+    assert_eq!(last[..3], last[3..]);
+}
+
+#[test]
+fn faces_without_normals_get_flat_counter_clockwise_ones() {
+    let meshes = load("flat_normals", "
+        v 0 0 0
+        v 0 0 -1
+        v 0 1 0
+        f 1 2 3
+    ");
+    let mesh = &meshes[0].mesh;
+    assert!(mesh.vertices.iter().all(|v| v.normal == [1.0, 0.0, 0.0]), "{:?}", mesh.vertices);
+}
+
+#[test]
+fn groups_and_materials_split_the_model() {
+    let path = write_files("groups", &[
+        ("model.obj", &format!("{}
+            mtllib model.mtl
+            o first
+            usemtl red
+            f 1 2 3
+            usemtl translucent
+            f 1 3 4
+            g second
+            f 1 2 3 4
+        ", QUAD)),
+        ("model.mtl", "
+            # two materials
+            newmtl red
+            Kd 1 0 0
+            Ks 0.2 0.8 0.4
+            Ns 64
+            newmtl translucent
+            Kd 0 0 1
+            d 0.5
+        "),
+    ]);
+    let meshes = Mesh::<Vertex3D>::from_obj(&path).unwrap();
+    let parts: Vec<_> = meshes.iter().map(|part| (part.name.as_str(), part.material.color, part.mesh.indices.len() / 3)).collect();
+    assert_eq!(parts, [
+        ("first", Color::RED, 1),
+        ("first", Color::new(0.0, 0.0, 1.0, 0.5), 1),
+        ("second", Color::new(0.0, 0.0, 1.0, 0.5), 2),
+    ]);
+    assert_eq!(meshes[0].material.shading, Shading::BlinnPhong { specular: 0.8, shininess: 64.0 });
+    assert_eq!(meshes[1].material.shading, Material3D::default().shading);
+}
+
+fn write_texture(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rgfx_obj_{}_{}", test, std::process::id())).join("texture.png");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image::RgbaImage::from_pixel(2, 2, image::Rgba([0, 255, 0, 255])).save(&path).expect("Failed to write texture");
+    path
+}
+
+#[test]
+fn textured_materials_are_drawn() {
+    let Some(mut renderer) = headless_renderer(32, 32) else { return };
+    let texture = write_texture("textured");
+    let path = write_files("textured", &[
+        ("model.obj", &format!("{}mtllib model.mtl\nusemtl green\nf 1/1/1 2/2/1 3/3/1 4/4/1\n", QUAD)),
+        ("model.mtl", "newmtl green\nKd 1 1 1\nmap_Kd -s 1 1 1 texture.png\n"),
+    ]);
+    let meshes = Mesh::<Vertex3D>::from_obj(&path).unwrap();
+    assert_eq!(meshes[0].material.image.as_ref().map(|image| PathBuf::from(&image.path)), Some(texture));
+
+    let mut camera = Camera::default_perspective(32.0, 32.0);
+    camera.update_viewport(32.0, 32.0);
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        for part in &meshes {
+            canvas.draw_mesh_3d(&mut camera, Transform::new(), part.mesh.clone(), part.material.clone());
+        }
+    });
+    assert_eq!(frame.image.to_rgba8().get_pixel(16, 16).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn missing_libraries_and_materials_use_the_default() {
+    let obj = format!("{}mtllib missing.mtl\nusemtl missing\nf 1 2 3\n", QUAD);
+    let meshes = load("missing_library", &obj);
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(meshes[0].material.color, Color::WHITE);
+    assert!(meshes[0].material.image.is_none());
+}
+
+#[test]
+fn malformed_files_are_errors() {
+    let cases = [
+        ("v 1 2 x", 1, "Invalid number x"),
+        ("v 1 2", 1, "Expected 3 numbers, found 2"),
+        ("v 0 0 0\nv 1 0 0\nf 1 2", 3, "Face has 2 corners, at least 3 are needed"),
+        ("v 0 0 0\nf 1 2 3", 2, "The position index 2 is out of range, there are 1"),
+        ("v 0 0 0\nf 0 1 1", 2, "The position index 0 is out of range, there are 1"),
+        ("v 0 0 0\nf 1/1 1 1", 2, "The texture coordinate index 1 is out of range, there are 0"),
+        ("v 0 0 0\nf 1/a 1 1", 2, "Invalid texture coordinate index a"),
+        ("v 0 0 0\nf 1/1/1/1 1 1", 2, "Face corner 1/1/1/1 has too many indices"),
+    ];
+    for (i, (obj, line, message)) in cases.iter().enumerate() {
+        let path = write_files(&format!("malformed_{}", i), &[("model.obj", obj)]);
+        let error = Mesh::<Vertex3D>::from_obj(&path).unwrap_err();
+        assert_eq!(error, ObjError::Parse { path: path.clone(), line: *line, message: message.to_string() });
+    }
+
+    let path = write_files("malformed_mtl", &[("model.obj", "mtllib model.mtl\n"), ("model.mtl", "Kd 1 1 1\n")]);
+    let error = Mesh::<Vertex3D>::from_obj(&path).unwrap_err();
+    assert!(matches!(&error, ObjError::Parse { path, line: 1, .. } if path.ends_with("model.mtl")), "{}", error);
+}
+
+#[test]
+fn missing_files_are_errors() {
+    let error = Mesh::<Vertex3D>::from_obj("does/not/exist.obj").unwrap_err();
+    assert!(matches!(error, ObjError::Io { .. }));
+    assert!(error.to_string().starts_with("Failed to read does/not/exist.obj"));
+}
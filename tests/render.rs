@@ -0,0 +1,786 @@
+use rgfx::prelude::*;
+use rgfx::testing::{headless_renderer, render_frame};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+
+fn camera() -> Camera {
+    let mut camera = Camera::default_orthographic(WIDTH as f32, HEIGHT as f32);
+    camera.update_viewport(WIDTH as f32, HEIGHT as f32);
+    camera
+}
+
+fn center_pixel(frame: &Image) -> [u8; 4] {
+    frame.image.to_rgba8().get_pixel(WIDTH / 2, HEIGHT / 2).0
+}
+
+#[test]
+fn draws_beyond_initial_uniform_capacity() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let red = Image::single_pixel(Color::RED);
+    let blue = Image::single_pixel(Color::BLUE);
+
+    // Alternating textures prevents the draws from being batched together
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        for i in 0..2500 {
+            let image = if i % 2 == 0 { red.clone() } else { blue.clone() };
+            canvas.draw_rectangle(&mut camera, Transform::new(), 1.0, 1.0, DrawStyle::default().with_image(image));
+        }
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+
+    let stats = renderer.stats();
+    assert_eq!(stats.draw_commands, 2500);
+    assert_eq!(stats.draw_calls, 2500);
+    assert!(stats.uniform_capacity >= 2500);
+    assert_eq!(stats.uniform_reallocations, 1);
+}
+
+#[test]
+fn batches_draws_sharing_camera_and_texture() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        for i in 0..20_000 {
+            let transform = Transform::new()
+                .with_position((i % 100) as f32 / 50.0 - 1.0, (i / 100) as f32 / 100.0 - 1.0, 0.0)
+                .with_rotation(0.0, 0.0, i as f32);
+            canvas.draw_rectangle(&mut camera, transform, 0.01, 0.01, DrawStyle::new(Color::RED));
+        }
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::GREEN));
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+
+    let stats = renderer.stats();
+    assert_eq!(stats.draw_commands, 20_001);
+    // 80004 vertices need two batches to stay within 16-bit indices
+    assert_eq!(stats.draw_calls, 2);
+}
+
+#[test]
+fn texture_changes_split_batches() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let image = Image::single_pixel(Color::WHITE);
+
+    render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::default().with_image(image.clone()));
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::default().with_image(image.clone()));
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+    });
+
+    assert_eq!(renderer.stats().draw_calls, 3);
+}
+
+#[test]
+fn geometry_arena_is_reused_across_frames() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+
+    let mut draw = |canvas: &mut Canvas| {
+        canvas.clear(Color::BLACK);
+        for _ in 0..20_000 {
+            canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+        }
+    };
+
+    render_frame(&mut renderer, &mut draw);
+    let first = renderer.stats();
+    assert!(first.arena_reallocations > 0);
+    assert!(first.vertex_arena_capacity >= 80_000 * 32);
+
+    render_frame(&mut renderer, &mut draw);
+    let second = renderer.stats();
+    assert_eq!(second.arena_reallocations, first.arena_reallocations);
+    assert_eq!(second.bytes_uploaded, first.bytes_uploaded);
+}
+
+#[test]
+fn instanced_draws_use_one_draw_call() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let instances: Vec<InstanceData> = (0..5000)
+        .map(|i| InstanceData::new(Transform::new().with_position(0.0, 0.0, 0.0)).with_tint(if i == 4999 { Color::BLUE } else { Color::RED }))
+        .collect();
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_instanced(&mut camera, MeshBuilder2D::from_rectangle(0.5, 0.5, Color::WHITE.into()), &instances, DrawStyle::default());
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+    assert_eq!(renderer.stats().draw_calls, 1);
+}
+
+#[test]
+fn transparent_color_is_blended() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLUE);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 1.0, 1.0, DrawStyle::new(Color::TRANSPARENT));
+    });
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLUE);
+        let style = DrawStyle::new(Color::TRANSPARENT).with_blend_mode(BlendMode::None);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 1.0, 1.0, style);
+    });
+    assert_eq!(center_pixel(&frame), [0, 0, 0, 0]);
+}
+
+#[test]
+fn stroked_shapes_are_hollow() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let style = DrawStyle::new(Color::RED).with_stroke(Stroke::new(0.1));
+        canvas.draw_rectangle(&mut camera(), Transform::new(), 1.0, 1.0, style);
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 0, 0, 255]);
+    assert_eq!(frame.image.to_rgba8().get_pixel(WIDTH / 2, HEIGHT / 4).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn translucent_strokes_blend_once_at_joins() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let points = [Point2::new(8.0, 16.0), Point2::new(48.0, 16.0), Point2::new(48.0, 56.0)];
+        let style = DrawStyle::new(Color::new(1.0, 1.0, 1.0, 0.5)).with_stroke(Stroke::new(12.0).with_join(LineJoin::Round));
+        canvas.draw_polyline(&mut canvas.screen_camera(), Transform::new(), &points, false, style);
+    });
+
+    let pixels = frame.image.to_rgba8();
+    let segment = pixels.get_pixel(24, 16).0;
+    assert_ne!(segment, [0, 0, 0, 255]);
+    for (x, y) in [(44, 20), (48, 16), (51, 13), (48, 32)] {
+        assert_eq!(pixels.get_pixel(x, y).0, segment, "pixel ({}, {}) differs from the middle of the stroke", x, y);
+    }
+}
+
+#[test]
+fn lines_without_a_stroke_are_one_pixel_wide() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_line(&mut camera, Transform::new(), Point2::new(-1.0, 0.01), Point2::new(1.0, 0.01), DrawStyle::new(Color::WHITE));
+    });
+
+    let lit = frame.image.to_rgba8().pixels().filter(|p| p.0 != [0, 0, 0, 255]).count();
+    assert!((WIDTH as usize..=2 * WIDTH as usize).contains(&lit), "{} pixels lit", lit);
+}
+
+#[test]
+fn circle_segments_adapt_to_screen_size() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    // A filled circle is a fan with one triangle per segment
+    let mut segments_for = |radius: f32, scale: f32| {
+        render_frame(&mut renderer, |canvas| {
+            canvas.clear(Color::BLACK);
+            canvas.draw_circle(&mut camera(), Transform::new().with_scale(scale, scale, 1.0), radius, DrawStyle::new(Color::RED));
+        });
+        renderer.stats().triangles
+    };
+
+    // The camera shows two units across the frame, and outlines may stray a quarter of a pixel from the true circle
+    let pixels_per_unit = HEIGHT as f32 / 2.0;
+    let tolerance = 0.25;
+    let mut previous = 0;
+    for (radius, scale) in [(0.05, 1.0), (0.25, 1.0), (1.0, 1.0), (1.0, 4.0), (1.0, 16.0)] {
+        let segments = segments_for(radius, scale);
+        let radius_px = radius * scale * pixels_per_unit;
+        // Chords stray furthest from the circle halfway along, by the sagitta
+        let chord_error = |segments: usize| radius_px * (1.0 - (std::f32::consts::PI / segments as f32).cos());
+
+        assert!(chord_error(segments) <= tolerance + 1e-4, "{} segments stray {} pixels from a {} pixel circle", segments, chord_error(segments), radius_px);
+        assert!(segments == 8 || chord_error(segments - 1) > tolerance, "{} segments are more than a {} pixel circle needs", segments, radius_px);
+        assert!(segments > previous, "{} segments for a {} pixel circle, {} for a smaller one", segments, radius_px, previous);
+        previous = segments;
+    }
+}
+
+#[test]
+fn screen_camera_uses_top_left_pixels() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let transform = Transform::new().with_position(12.0, 8.0, 0.0);
+        canvas.draw_rectangle(&mut canvas.screen_camera(), transform, 16.0, 8.0, DrawStyle::new(Color::RED));
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(5, 5).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(19, 10).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(21, 5).0, [0, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(5, 13).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn screen_camera_keeps_images_upright() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let image = image::RgbaImage::from_fn(1, 2, |_, y| {
+        if y == 0 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 255]) }
+    });
+    let image = Image {
+        path: "render_top_red_bottom_blue".to_string(),
+        image: image::DynamicImage::ImageRgba8(image),
+    };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let transform = Transform::new().with_position(32.0, 32.0, 0.0);
+        let style = DrawStyle::default().with_image(image);
+        canvas.draw_rectangle(&mut canvas.screen_camera(), transform, 64.0, 64.0, style);
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(32, 8).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(32, 56).0, [0, 0, 255, 255]);
+}
+
+/// Shows the default two units across the frame, mirrored left to right.
+struct MirroredCamera;
+
+impl CameraMatrix for MirroredCamera {
+    fn to_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::from_nonuniform_scale(-1.0, 1.0, 1.0)
+    }
+}
+
+#[test]
+fn horizontally_mirrored_cameras_keep_images_the_right_way_round() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let image = image::RgbaImage::from_fn(32, 32, |x, y| {
+        match (x < 16, y < 16) {
+            (true, true) => image::Rgba([255, 0, 0, 255]),
+            (false, true) => image::Rgba([0, 255, 0, 255]),
+            _ => image::Rgba([0, 0, 255, 255]),
+        }
+    });
+    let image = Image {
+        path: "render_red_green_over_blue".to_string(),
+        image: image::DynamicImage::ImageRgba8(image),
+    };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut MirroredCamera, Transform::new(), 2.0, 2.0, DrawStyle::default().with_image(image));
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(16, 16).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(48, 16).0, [0, 255, 0, 255]);
+    assert_eq!(pixels.get_pixel(32, 48).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn pushed_transforms_apply_to_children() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.push_transform(Transform::new().with_position(0.5, 0.0, 0.0));
+        canvas.with_transform(Transform::new().with_scale(2.0, 2.0, 1.0), |canvas| {
+            canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.0, 0.25, 0.0), 0.2, 0.2, DrawStyle::new(Color::RED));
+        });
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.0, -0.5, 0.0), 0.2, 0.2, DrawStyle::new(Color::GREEN));
+        canvas.pop_transform();
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.2, 0.2, DrawStyle::new(Color::BLUE));
+    });
+
+    // Pixel centers of world points on the 64 x 64 frame
+    let pixels = frame.image.to_rgba8();
+    let at = |x: f32, y: f32| pixels.get_pixel(((x + 1.0) * 32.0) as u32, ((1.0 - y) * 32.0) as u32).0;
+    assert_eq!(at(0.5, 0.5), [255, 0, 0, 255]);
+    assert_eq!(at(0.65, 0.35), [255, 0, 0, 255]);
+    assert_eq!(at(0.5, -0.5), [0, 255, 0, 255]);
+    assert_eq!(at(0.0, 0.0), [0, 0, 255, 255]);
+}
+
+#[test]
+fn pushed_transforms_apply_to_instances() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.with_transform(Transform::new().with_position(-0.5, 0.5, 0.0), |canvas| {
+            let instances = [InstanceData::new(Transform::new()).with_tint(Color::RED)];
+            canvas.draw_instanced(&mut camera(), MeshBuilder2D::from_rectangle(0.2, 0.2, Color::WHITE.into()), &instances, DrawStyle::default());
+        });
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(16, 16).0, [255, 0, 0, 255]);
+    assert_eq!(center_pixel(&frame), [0, 0, 0, 255]);
+}
+
+#[test]
+fn clip_rects_intersect_and_restore() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.push_clip_rect(0.0, 0.0, 32.0, 32.0);
+        canvas.push_clip_rect(16.0, 16.0, 32.0, 32.0);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 2.0, 2.0, DrawStyle::new(Color::RED));
+        canvas.pop_clip_rect();
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(-0.75, 0.75, 0.0), 0.5, 0.5, DrawStyle::new(Color::GREEN));
+        canvas.pop_clip_rect();
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.75, -0.75, 0.0), 0.5, 0.5, DrawStyle::new(Color::BLUE));
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(20, 20).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(36, 36).0, [0, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(12, 20).0, [0, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(4, 4).0, [0, 255, 0, 255]);
+    assert_eq!(pixels.get_pixel(60, 60).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn world_clip_rect_follows_the_camera() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.push_world_clip_rect(&mut camera, Transform::new().with_position(0.5, 0.5, 0.0), 1.0, 1.0);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 2.0, 2.0, DrawStyle::new(Color::RED));
+        canvas.pop_clip_rect();
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(48, 16).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(16, 16).0, [0, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(48, 48).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn world_clip_rect_at_or_behind_the_camera_clips_everything() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let mut perspective = Camera::default_perspective(WIDTH as f32, HEIGHT as f32);
+    perspective.update_viewport(WIDTH as f32, HEIGHT as f32);
+    for z in [5.0, 10.0] {
+        let frame = render_frame(&mut renderer, |canvas| {
+            canvas.clear(Color::BLACK);
+            canvas.push_world_clip_rect(&mut perspective, Transform::new().with_position(0.0, 0.0, z), 2.0, 2.0);
+            canvas.draw_rectangle(&mut camera, Transform::new().with_position(-0.5, 0.0, 0.0), 1.0, 2.0, DrawStyle::new(Color::RED));
+            canvas.pop_clip_rect();
+            canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.5, 0.0, 0.0), 1.0, 2.0, DrawStyle::new(Color::BLUE));
+        });
+
+        let pixels = frame.image.to_rgba8();
+        assert_eq!(pixels.get_pixel(16, 32).0, [0, 0, 0, 255], "clip rect at z = {z}");
+        assert_eq!(pixels.get_pixel(48, 32).0, [0, 0, 255, 255], "clip rect at z = {z}");
+    }
+}
+
+#[test]
+fn clip_changes_split_batches() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+        canvas.push_clip_rect(0.0, 0.0, 32.0, 32.0);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+        canvas.push_clip_rect(100.0, 100.0, 10.0, 10.0);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.5, 0.5, DrawStyle::new(Color::RED));
+        canvas.pop_clip_rect();
+        canvas.pop_clip_rect();
+    });
+
+    // The draw clipped away entirely is skipped
+    assert_eq!(renderer.stats().draw_calls, 2);
+}
+
+#[test]
+fn masks_limit_drawing_to_their_mesh() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let circle = || MeshBuilder2D::from_circle(0.5, 32, Color::WHITE.into());
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.push_mask(&mut camera, Transform::new().with_position(-0.5, 0.0, 0.0), circle(), MaskMode::Inside);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 2.0, 2.0, DrawStyle::new(Color::RED));
+        canvas.pop_mask();
+        canvas.push_mask(&mut camera, Transform::new().with_position(0.5, 0.0, 0.0), circle(), MaskMode::Outside);
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.5, 0.0, 0.0), 1.0, 1.0, DrawStyle::new(Color::GREEN));
+        canvas.pop_mask();
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(16, 32).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(16, 4).0, [0, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(48, 32).0, [0, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(34, 17).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn nested_masks_combine_and_pop() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.push_mask(&mut camera, Transform::new(), MeshBuilder2D::from_circle(0.9, 64, Color::WHITE.into()), MaskMode::Inside);
+        canvas.push_mask(&mut camera, Transform::new(), MeshBuilder2D::from_circle(0.4, 64, Color::WHITE.into()), MaskMode::Outside);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 2.0, 2.0, DrawStyle::new(Color::RED));
+        canvas.pop_mask();
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.0, -0.5, 0.0), 2.0, 1.0, DrawStyle::new(Color::BLUE));
+        canvas.pop_mask();
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(-0.9, 0.9, 0.0), 0.2, 0.2, DrawStyle::new(Color::GREEN));
+    });
+
+    let pixels = frame.image.to_rgba8();
+    // Ring between the two circles
+    assert_eq!(pixels.get_pixel(32, 12).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(32, 30).0, [0, 0, 0, 255]);
+    // Lower half of the outer circle after popping the inner mask
+    assert_eq!(pixels.get_pixel(32, 40).0, [0, 0, 255, 255]);
+    assert_eq!(pixels.get_pixel(2, 62).0, [0, 0, 0, 255]);
+    // Everything after popping both masks
+    assert_eq!(pixels.get_pixel(1, 1).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn render_targets_can_be_drawn_as_images() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let target = RenderTarget::new(16, 16);
+
+    // Left half red, right half blue, drawn in the target's own pixel space
+    let mut target_canvas = target.canvas();
+    target_canvas.clear(Color::RED);
+    let transform = Transform::new().with_position(12.0, 8.0, 0.0);
+    target_canvas.draw_rectangle(&mut target_canvas.screen_camera(), transform, 8.0, 16.0, DrawStyle::new(Color::BLUE));
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.render_to_target(&target, target_canvas);
+        let transform = Transform::new().with_position(32.0, 32.0, 0.0);
+        let style = DrawStyle::default().with_image(target.image());
+        canvas.draw_rectangle(&mut canvas.screen_camera(), transform, 32.0, 32.0, style);
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(20, 32).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(44, 32).0, [0, 0, 255, 255]);
+    assert_eq!(pixels.get_pixel(8, 8).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn render_targets_keep_their_contents_between_frames() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let target = RenderTarget::new(8, 8);
+
+    let mut target_canvas = target.canvas();
+    target_canvas.clear(Color::GREEN);
+    render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.render_to_target(&target, target_canvas);
+    });
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 8.0, 8.0, DrawStyle::default().with_image(target.image()));
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+}
+
+#[test]
+fn render_targets_can_be_nested() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let inner = RenderTarget::new(4, 4);
+    let outer = RenderTarget::new(8, 8);
+
+    let mut inner_canvas = inner.canvas();
+    inner_canvas.clear(Color::BLUE);
+    let mut outer_canvas = outer.canvas();
+    outer_canvas.clear(Color::RED);
+    outer_canvas.render_to_target(&inner, inner_canvas);
+    let transform = Transform::new().with_position(4.0, 4.0, 0.0);
+    outer_canvas.draw_rectangle(&mut outer_canvas.screen_camera(), transform, 4.0, 4.0, DrawStyle::default().with_image(inner.image()));
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.render_to_target(&outer, outer_canvas);
+        let transform = Transform::new().with_position(32.0, 32.0, 0.0);
+        canvas.draw_rectangle(&mut canvas.screen_camera(), transform, 64.0, 64.0, DrawStyle::default().with_image(outer.image()));
+    });
+
+    let pixels = frame.image.to_rgba8();
+    assert_eq!(pixels.get_pixel(32, 32).0, [0, 0, 255, 255]);
+    assert_eq!(pixels.get_pixel(4, 4).0, [255, 0, 0, 255]);
+    assert_eq!(renderer.stats().draw_commands, 2);
+}
+
+#[test]
+fn dropped_render_targets_release_their_textures() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let target = RenderTarget::new(8, 8);
+    let clone = target.clone();
+
+    let mut target_canvas = target.canvas();
+    target_canvas.clear(Color::GREEN);
+    render_frame(&mut renderer, |canvas| canvas.render_to_target(&target, target_canvas));
+    assert_eq!(renderer.stats().render_targets, 1);
+
+    drop(target);
+    render_frame(&mut renderer, |canvas| canvas.clear(Color::BLACK));
+    assert_eq!(renderer.stats().render_targets, 1);
+
+    drop(clone);
+    render_frame(&mut renderer, |canvas| canvas.clear(Color::BLACK));
+    assert_eq!(renderer.stats().render_targets, 0);
+}
+
+#[test]
+fn unrendered_targets_draw_untextured() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let target = RenderTarget::new(8, 8);
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 8.0, 8.0, DrawStyle::new(Color::GREEN).with_image(target.image()));
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+}
+
+#[test]
+fn grayscale_removes_color() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.add_post_effect(PostEffect::grayscale(1.0));
+        canvas.draw_rectangle(&mut camera, Transform::new(), 1.0, 1.0, DrawStyle::new(Color::RED));
+    });
+
+    let [r, g, b, a] = center_pixel(&frame);
+    assert!(r > 64 && r == g && g == b, "Expected gray, got {:?}", [r, g, b]);
+    assert_eq!(a, 255);
+    assert_eq!(renderer.stats().draw_calls, 2);
+}
+
+#[test]
+fn post_effects_apply_in_order() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let swap_red_and_green = PostEffect::new("
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            let color = textureSample(source_texture, source_sampler, in.uv);
+            return vec4<f32>(color.g, color.r, color.b, color.a);
+        }
+    ");
+    let scale_red = PostEffect::new("
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            let color = textureSample(source_texture, source_sampler, in.uv);
+            return vec4<f32>(color.r * post.params.x, color.g, color.b, color.a);
+        }
+    ").with_params([0.0; 4]);
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::RED);
+        canvas.add_post_effect(swap_red_and_green.clone());
+        canvas.add_post_effect(scale_red.clone());
+    });
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::RED);
+        canvas.add_post_effect(scale_red);
+        canvas.add_post_effect(swap_red_and_green);
+    });
+    assert_eq!(center_pixel(&frame), [0, 0, 0, 255]);
+}
+
+#[test]
+fn identity_lut_keeps_colors() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let colors = [Color::RED, Color::ORANGE, Color::new(0.2, 0.6, 0.9, 1.0), Color::new(0.5, 0.5, 0.1, 1.0)];
+    let draw = |canvas: &mut Canvas, camera: &mut Camera| {
+        canvas.clear(Color::BLACK);
+        for (i, color) in colors.iter().enumerate() {
+            let transform = Transform::new().with_position(i as f32 * 0.5 - 0.75, 0.0, 0.0);
+            canvas.draw_rectangle(camera, transform, 0.5, 2.0, DrawStyle::new(*color));
+        }
+    };
+
+    let plain = render_frame(&mut renderer, |canvas| draw(canvas, &mut camera)).image.to_rgba8();
+    let graded = render_frame(&mut renderer, |canvas| {
+        canvas.add_post_effect(PostEffect::lut(PostEffect::identity_lut(16)));
+        draw(canvas, &mut camera);
+    }).image.to_rgba8();
+
+    for (plain, graded) in plain.pixels().zip(graded.pixels()) {
+        for channel in 0..4 {
+            assert!(plain.0[channel].abs_diff(graded.0[channel]) <= 2, "{:?} became {:?}", plain, graded);
+        }
+    }
+}
+
+#[test]
+fn invalid_post_effects_are_skipped() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLUE);
+        canvas.add_post_effect(PostEffect::new("this is not wgsl"));
+        canvas.add_post_effect(PostEffect::grayscale(1.0));
+    });
+
+    let [r, g, b, _] = center_pixel(&frame);
+    assert!(r == g && g == b);
+    assert_eq!(renderer.stats().draw_calls, 1);
+}
+
+const PARAMS_MATERIAL: &str = "
+    @fragment
+    fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+        return material.params[0] * base_color(in);
+    }
+";
+
+#[test]
+fn materials_use_their_params() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let material = Material::new(PARAMS_MATERIAL).with_params(&[0.0, 1.0, 0.0, 1.0]);
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 1.0, 1.0, DrawStyle::new(Color::WHITE).with_material(material));
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+}
+
+#[test]
+fn materials_sample_their_textures() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let material = Material::new("
+        @vertex
+        fn vs_main(in: VertexInput) -> VertexOutput {
+            return default_vertex(in);
+        }
+
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            return textureSample(texture_0, sampler_0, in.uv) + textureSample(texture_1, sampler_1, in.uv);
+        }
+    ").with_texture(Image::single_pixel(Color::RED)).with_texture(Image::single_pixel(Color::BLUE));
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 1.0, 1.0, DrawStyle::default().with_material(material));
+    });
+
+    assert_eq!(center_pixel(&frame), [255, 0, 255, 255]);
+}
+
+#[test]
+fn draws_sharing_a_material_are_batched() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let red = Material::new(PARAMS_MATERIAL).with_params(&[1.0, 0.0, 0.0, 1.0]);
+    let blue = Material::new(PARAMS_MATERIAL).with_params(&[0.0, 0.0, 1.0, 1.0]);
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        for i in 0..4 {
+            let transform = Transform::new().with_position(i as f32 * 0.5 - 0.75, 0.0, 0.0);
+            canvas.draw_rectangle(&mut camera, transform, 0.25, 0.25, DrawStyle::default().with_material(red.clone()));
+        }
+        canvas.draw_rectangle(&mut camera, Transform::new(), 0.25, 0.25, DrawStyle::default().with_material(blue));
+        canvas.draw_rectangle(&mut camera, Transform::new().with_position(0.0, 0.5, 0.0), 0.25, 0.25, DrawStyle::default());
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 0, 255, 255]);
+    assert_eq!(renderer.stats().draw_calls, 3);
+}
+
+#[test]
+fn invalid_materials_fall_back_to_the_built_in_shader() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let material = Material::new("fn fs_main() -> oops");
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_rectangle(&mut camera, Transform::new(), 1.0, 1.0, DrawStyle::new(Color::GREEN).with_material(material));
+    });
+
+    assert_eq!(center_pixel(&frame), [0, 255, 0, 255]);
+}
+
+#[test]
+fn text_survives_the_glyph_atlas_filling_up() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let font = Font::from_file("examples/assets/DejaVuSans.ttf").expect("Failed to load font");
+    // Enough large glyphs to overflow the largest atlas, clearing it after the block was prepared
+    let filler: String = (0x21..0x7f).chain(0xa1..0x250).chain(0x370..0x530).filter_map(char::from_u32).collect();
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let mut screen = canvas.screen_camera();
+        canvas.draw_text(&mut screen, Transform::new().with_position(-32.0, -64.0, 0.0), "\u{2588}", &font, 256.0, DrawStyle::new(Color::WHITE));
+        canvas.draw_text(&mut screen, Transform::new().with_position(1000.0, 1000.0, 0.0), &filler, &font, 256.0, DrawStyle::new(Color::WHITE));
+    });
+
+    assert_eq!(center_pixel(&frame), [255, 255, 255, 255]);
+}
+
+#[test]
+fn new_glyphs_upload_only_their_part_of_the_atlas() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let font = Font::from_file("examples/assets/DejaVuSans.ttf").expect("Failed to load font");
+    let draw = |canvas: &mut Canvas, block: bool| {
+        canvas.clear(Color::BLACK);
+        let mut screen = canvas.screen_camera();
+        canvas.draw_text(&mut screen, Transform::new(), "rgfx", &font, 12.0, DrawStyle::new(Color::WHITE));
+        if block {
+            canvas.draw_text(&mut screen, Transform::new().with_position(-32.0, -64.0, 0.0), "\u{2588}", &font, 256.0, DrawStyle::new(Color::WHITE));
+        }
+    };
+
+    render_frame(&mut renderer, |canvas| draw(canvas, false));
+    let atlas_bytes = renderer.stats().texture_bytes_uploaded;
+    assert!(atlas_bytes > 0);
+
+    let frame = render_frame(&mut renderer, |canvas| draw(canvas, true));
+    assert_eq!(center_pixel(&frame), [255, 255, 255, 255]);
+    let glyph_bytes = renderer.stats().texture_bytes_uploaded;
+    assert!(glyph_bytes > 0 && glyph_bytes < atlas_bytes / 2, "uploaded {} bytes of a {} byte atlas", glyph_bytes, atlas_bytes);
+
+    render_frame(&mut renderer, |canvas| draw(canvas, true));
+    assert_eq!(renderer.stats().texture_bytes_uploaded, 0);
+}
+
+#[test]
+fn images_cannot_replace_the_glyph_atlas() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let font = Font::from_file("examples/assets/DejaVuSans.ttf").expect("Failed to load font");
+    let image = Image { path: "__glyph_atlas".to_string(), ..Image::single_pixel(Color::RED) };
+
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        let mut screen = canvas.screen_camera();
+        canvas.draw_rectangle(&mut screen, Transform::new().with_position(32.0, 32.0, 0.0), 64.0, 64.0, DrawStyle::default().with_image(image));
+        canvas.draw_text(&mut screen, Transform::new(), "rgfx", &font, 12.0, DrawStyle::new(Color::WHITE));
+    });
+
+    assert_eq!(center_pixel(&frame), [255, 0, 0, 255]);
+}
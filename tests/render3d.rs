@@ -0,0 +1,128 @@
+use rgfx::prelude::*;
+use rgfx::testing::{headless_renderer, render_frame};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+
+fn camera() -> Camera {
+    let mut camera = Camera::default_perspective(WIDTH as f32, HEIGHT as f32);
+    camera.update_viewport(WIDTH as f32, HEIGHT as f32);
+    camera
+}
+
+/// A square facing +z, counter-clockwise when seen from the front.
+fn quad(size: f32) -> Mesh<Vertex3D> {
+    let h = size / 2.0;
+    let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex3D { position: [x, y, 0.0], normal: [0.0, 0.0, 1.0], uv: [u, v] };
+    Mesh {
+        vertices: vec![vertex(-h, -h, 0.0, 1.0), vertex(h, -h, 1.0, 1.0), vertex(h, h, 1.0, 0.0), vertex(-h, h, 0.0, 0.0)],
+        indices: vec![0, 1, 2, 0, 2, 3],
+    }
+}
+
+fn pixel(frame: &Image, x: u32, y: u32) -> [u8; 4] {
+    frame.image.to_rgba8().get_pixel(x, y).0
+}
+
+#[test]
+fn nearer_meshes_hide_farther_ones() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let near = Transform::new().with_position(0.0, 0.0, 1.0);
+    let far = Transform::new().with_position(0.0, 0.0, -1.0);
+
+    for near_first in [true, false] {
+        let frame = render_frame(&mut renderer, |canvas| {
+            canvas.clear(Color::BLACK);
+            if near_first {
+                canvas.draw_mesh_3d(&mut camera, near, quad(1.0), Material3D::new(Color::RED));
+                canvas.draw_mesh_3d(&mut camera, far, quad(4.0), Material3D::new(Color::BLUE));
+            } else {
+                canvas.draw_mesh_3d(&mut camera, far, quad(4.0), Material3D::new(Color::BLUE));
+                canvas.draw_mesh_3d(&mut camera, near, quad(1.0), Material3D::new(Color::RED));
+            }
+        });
+
+        assert_eq!(pixel(&frame, 32, 32), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 16, 32), [0, 0, 255, 255]);
+    }
+}
+
+#[test]
+fn back_faces_are_culled() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_rotation(0.0, 180.0, 0.0), quad(2.0), Material3D::new(Color::RED));
+    });
+
+    assert_eq!(pixel(&frame, 32, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+fn perspective_makes_distant_meshes_smaller() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_position(-1.0, 0.0, 0.0), quad(1.0), Material3D::new(Color::RED));
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_position(1.5, 0.0, -10.0), quad(1.0), Material3D::new(Color::GREEN));
+    });
+
+    let image = frame.image.to_rgba8();
+    let count = |color: [u8; 4]| image.pixels().filter(|p| p.0 == color).count();
+    let red = count([255, 0, 0, 255]);
+    let green = count([0, 255, 0, 255]);
+    assert!(green > 0 && red > green * 4, "red {} green {}", red, green);
+}
+
+#[test]
+fn depth_buffer_follows_resize() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    renderer.resize(96, 48);
+    let mut camera = Camera::default_perspective(96.0, 48.0);
+    camera.update_viewport(96.0, 48.0);
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_position(0.0, 0.0, 1.0), quad(1.0), Material3D::new(Color::RED));
+        canvas.draw_mesh_3d(&mut camera, Transform::new(), quad(2.0), Material3D::new(Color::BLUE));
+    });
+
+    assert_eq!(pixel(&frame, 48, 24), [255, 0, 0, 255]);
+}
+
+#[test]
+fn materials_can_be_textured() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let image = image::RgbaImage::from_fn(16, 16, |_, y| {
+        if y < 8 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 255]) }
+    });
+    let image = Image {
+        path: "render3d_top_red_bottom_blue".to_string(),
+        image: image::DynamicImage::ImageRgba8(image),
+    };
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_mesh_3d(&mut camera, Transform::new(), quad(3.0), Material3D::default().with_image(image));
+    });
+
+    assert_eq!(pixel(&frame, 32, 20), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 44), [0, 0, 255, 255]);
+}
+
+#[test]
+fn later_2d_draws_overlay_3d_meshes() {
+    let Some(mut renderer) = headless_renderer(WIDTH, HEIGHT) else { return };
+    let mut camera = camera();
+    let frame = render_frame(&mut renderer, |canvas| {
+        canvas.clear(Color::BLACK);
+        canvas.draw_mesh_3d(&mut camera, Transform::new().with_position(0.0, 0.0, 2.0), quad(2.0), Material3D::new(Color::RED));
+        let transform = Transform::new().with_position(32.0, 32.0, 0.0);
+        canvas.draw_rectangle(&mut canvas.screen_camera(), transform, 8.0, 8.0, DrawStyle::new(Color::GREEN));
+    });
+
+    assert_eq!(pixel(&frame, 32, 32), [0, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 24), [255, 0, 0, 255]);
+}
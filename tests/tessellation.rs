@@ -0,0 +1,281 @@
+use rgfx::prelude::*;
+
+/// Sums the signed area of the mesh triangles, positive when they are wound clockwise.
+fn clockwise_area(mesh: &Mesh<Vertex2D>) -> f32 {
+    mesh.indices.chunks(3)
+        .map(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| mesh.vertices[i as usize].position);
+            -((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+        })
+        .sum()
+}
+
+fn all_clockwise(mesh: &Mesh<Vertex2D>) -> bool {
+    mesh.indices.chunks(3).all(|t| {
+        let [a, b, c] = [t[0], t[1], t[2]].map(|i| mesh.vertices[i as usize].position);
+        (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]) < 0.0
+    })
+}
+
+#[test]
+fn butt_line_covers_length_times_width() {
+    let points = [Point2::new(0.0, 0.0), Point2::new(2.0, 0.0)];
+    let mesh = MeshBuilder2D::from_polyline(&points, false, &Stroke::new(0.5), Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn square_cap_extends_by_half_the_width() {
+    let points = [Point2::new(0.0, 0.0), Point2::new(0.0, 2.0)];
+    let stroke = Stroke::new(0.5).with_cap(LineCap::Square);
+    let mesh = MeshBuilder2D::from_polyline(&points, false, &stroke, Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - 1.25).abs() < 1e-5);
+}
+
+#[test]
+fn round_cap_adds_half_circles() {
+    let points = [Point2::new(0.0, 0.0), Point2::new(1.0, 1.0)];
+    let stroke = Stroke::new(1.0).with_cap(LineCap::Round);
+    let mesh = MeshBuilder2D::from_polyline(&points, false, &stroke, Color::WHITE.into());
+    let expected = 2.0_f32.sqrt() + std::f32::consts::PI * 0.25;
+    assert!((clockwise_area(&mesh) - expected).abs() < 0.01);
+}
+
+#[test]
+fn joins_are_wound_for_the_2d_pipeline() {
+    let points = [Point2::new(-1.0, -1.0), Point2::new(0.0, 1.0), Point2::new(1.0, -1.0), Point2::new(2.0, 0.5)];
+    for join in [LineJoin::Miter, LineJoin::Round, LineJoin::Bevel] {
+        for closed in [false, true] {
+            let stroke = Stroke::new(0.2).with_join(join);
+            let mesh = MeshBuilder2D::from_polyline(&points, closed, &stroke, Color::WHITE.into());
+            assert!(!mesh.indices.is_empty());
+            assert!(all_clockwise(&mesh), "{:?} join has triangles that would be culled", join);
+        }
+    }
+}
+
+#[test]
+fn sharp_miter_falls_back_to_bevel() {
+    let points = [Point2::new(-1.0, 0.0), Point2::new(1.0, 0.0), Point2::new(-1.0, 0.1)];
+    let stroke = Stroke::new(0.2).with_join(LineJoin::Miter);
+    let mesh = MeshBuilder2D::from_polyline(&points, false, &stroke, Color::WHITE.into());
+    let max_x = mesh.vertices.iter().map(|v| v.position[0]).fold(f32::MIN, f32::max);
+    assert!(max_x < 1.2);
+}
+
+#[test]
+fn joins_do_not_overlap_the_segments() {
+    // Two 10 x 2 segments meeting at a right angle share a 1 x 1 square on the inside of the turn
+    let points = [Point2::new(0.0, 0.0), Point2::new(10.0, 0.0), Point2::new(10.0, 10.0)];
+    let corner = std::f32::consts::PI / 4.0;
+    for (join, outer) in [(LineJoin::Miter, 1.0), (LineJoin::Bevel, 0.5), (LineJoin::Round, corner)] {
+        let mesh = MeshBuilder2D::from_polyline(&points, false, &Stroke::new(2.0).with_join(join), Color::WHITE.into());
+        let expected = 39.0 + outer;
+        assert!((clockwise_area(&mesh) - expected).abs() < 0.01, "{:?} join covers {} instead of {}", join, clockwise_area(&mesh), expected);
+    }
+
+    let square = [Point2::new(0.0, 0.0), Point2::new(10.0, 0.0), Point2::new(10.0, 10.0), Point2::new(0.0, 10.0)];
+    let mesh = MeshBuilder2D::from_polyline(&square, true, &Stroke::new(2.0), Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - (12.0 * 12.0 - 8.0 * 8.0)).abs() < 1e-3, "{}", clockwise_area(&mesh));
+}
+
+#[test]
+fn degenerate_polylines_are_empty() {
+    let stroke = Stroke::new(0.1);
+    let single = MeshBuilder2D::from_polyline(&[Point2::new(1.0, 1.0)], false, &stroke, Color::WHITE.into());
+    let repeated = MeshBuilder2D::from_polyline(&[Point2::new(1.0, 1.0); 3], true, &stroke, Color::WHITE.into());
+    assert!(single.indices.is_empty());
+    assert!(repeated.indices.is_empty());
+}
+
+fn square(path: Path, x: f32, y: f32, size: f32) -> Path {
+    path.move_to(Point2::new(x, y))
+        .line_to(Point2::new(x + size, y))
+        .line_to(Point2::new(x + size, y + size))
+        .line_to(Point2::new(x, y + size))
+        .close()
+}
+
+fn pentagram() -> Path {
+    let point = |i: usize| {
+        let angle = std::f32::consts::FRAC_PI_2 + i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+        Point2::new(angle.cos(), angle.sin())
+    };
+    (1..5).fold(Path::new().move_to(point(0)), |path, i| path.line_to(point(i))).close()
+}
+
+#[test]
+fn filled_square_covers_its_area() {
+    let mesh = square(Path::new(), 0.0, 0.0, 2.0).fill(FillRule::NonZero, Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - 4.0).abs() < 1e-4);
+    assert!(all_clockwise(&mesh));
+}
+
+#[test]
+fn fill_rules_differ_for_nested_contours() {
+    let path = square(square(Path::new(), 0.0, 0.0, 3.0), 1.0, 1.0, 1.0);
+    let non_zero = path.fill(FillRule::NonZero, Color::WHITE.into());
+    let even_odd = path.fill(FillRule::EvenOdd, Color::WHITE.into());
+    assert!((clockwise_area(&non_zero) - 9.0).abs() < 1e-4);
+    assert!((clockwise_area(&even_odd) - 8.0).abs() < 1e-4);
+}
+
+#[test]
+fn fill_rules_differ_for_self_intersecting_contours() {
+    let non_zero = pentagram().fill(FillRule::NonZero, Color::WHITE.into());
+    let even_odd = pentagram().fill(FillRule::EvenOdd, Color::WHITE.into());
+    assert!(all_clockwise(&non_zero));
+    assert!(all_clockwise(&even_odd));
+
+    // The even-odd rule leaves the inner pentagon empty
+    let inner_radius = (2.0 * std::f32::consts::PI / 5.0).cos() / (std::f32::consts::PI / 5.0).cos();
+    let pentagon = 2.5 * inner_radius * inner_radius * (2.0 * std::f32::consts::PI / 5.0).sin();
+    assert!((clockwise_area(&non_zero) - clockwise_area(&even_odd) - pentagon).abs() < 1e-3);
+}
+
+#[test]
+fn paths_with_many_edges_are_filled() {
+    // Every vertex starts a band, too many for a trapezoid of four vertices each to fit 16-bit indices
+    let edges = 20_000;
+    let path = (0..edges).fold(Path::new(), |path, i| {
+        let angle = std::f32::consts::TAU * i as f32 / edges as f32 + 0.1;
+        let point = Point2::new(angle.cos(), angle.sin());
+        if i == 0 { path.move_to(point) } else { path.line_to(point) }
+    });
+    let mesh = path.close().fill(FillRule::NonZero, Color::WHITE.into());
+    assert!(!mesh.vertices.is_empty());
+    assert!(all_clockwise(&mesh));
+    assert!((clockwise_area(&mesh) - std::f32::consts::PI).abs() < 0.01);
+}
+
+#[test]
+fn cubic_curves_approximate_a_circle() {
+    // Four cubic Bezier curves closely approximate a unit circle
+    let k = 0.552_284_8;
+    let path = Path::new()
+        .with_tolerance(0.001)
+        .move_to(Point2::new(1.0, 0.0))
+        .cubic_to(Point2::new(1.0, k), Point2::new(k, 1.0), Point2::new(0.0, 1.0))
+        .cubic_to(Point2::new(-k, 1.0), Point2::new(-1.0, k), Point2::new(-1.0, 0.0))
+        .cubic_to(Point2::new(-1.0, -k), Point2::new(-k, -1.0), Point2::new(0.0, -1.0))
+        .cubic_to(Point2::new(k, -1.0), Point2::new(1.0, -k), Point2::new(1.0, 0.0))
+        .close();
+    let mesh = path.fill(FillRule::NonZero, Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - std::f32::consts::PI).abs() < 0.01);
+}
+
+#[test]
+fn quad_curves_follow_their_control_point() {
+    // The area under a parabola through (0, 0), (1, 2 / 2) and (2, 0) is 4 / 3 of the control height over 2
+    let path = Path::new()
+        .with_tolerance(0.001)
+        .move_to(Point2::new(0.0, 0.0))
+        .quad_to(Point2::new(1.0, 2.0), Point2::new(2.0, 0.0))
+        .close();
+    let mesh = path.fill(FillRule::NonZero, Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - 4.0 / 3.0).abs() < 0.01);
+}
+
+#[test]
+fn arc_to_rounds_corners() {
+    let path = Path::new()
+        .with_tolerance(0.0005)
+        .move_to(Point2::new(0.0, 0.0))
+        .arc_to(Point2::new(1.0, 0.0), Point2::new(1.0, 1.0), 0.5)
+        .line_to(Point2::new(1.0, 1.0))
+        .line_to(Point2::new(0.0, 1.0))
+        .close();
+    let mesh = path.fill(FillRule::NonZero, Color::WHITE.into());
+    let cut_corner = 0.25 - std::f32::consts::PI * 0.25 / 4.0;
+    assert!((clockwise_area(&mesh) - (1.0 - cut_corner)).abs() < 1e-3);
+}
+
+#[test]
+fn stroked_path_keeps_contours_separate() {
+    let path = Path::new()
+        .move_to(Point2::new(0.0, 0.0))
+        .line_to(Point2::new(1.0, 0.0))
+        .move_to(Point2::new(0.0, 2.0))
+        .line_to(Point2::new(1.0, 2.0));
+    let mesh = path.stroke(&Stroke::new(0.5), Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - 1.0).abs() < 1e-5);
+}
+
+fn points(coords: &[[f32; 2]]) -> Vec<Point2<f32>> {
+    coords.iter().map(|[x, y]| Point2::new(*x, *y)).collect()
+}
+
+#[test]
+fn concave_polygon_is_triangulated() {
+    let l_shape = points(&[[0.0, 0.0], [3.0, 0.0], [3.0, 1.0], [1.0, 1.0], [1.0, 3.0], [0.0, 3.0]]);
+    let mesh = MeshBuilder2D::from_polygon(&l_shape, &[], Color::WHITE.into());
+    assert_eq!(mesh.indices.len(), 4 * 3);
+    assert!(all_clockwise(&mesh));
+    assert!((clockwise_area(&mesh) - 5.0).abs() < 1e-5);
+}
+
+#[test]
+fn polygon_winding_does_not_matter() {
+    let mut comb = points(&[[0.0, 0.0], [4.0, 0.0], [4.0, 2.0], [3.0, 1.0], [2.0, 2.0], [1.0, 1.0], [0.0, 2.0]]);
+    let forward = MeshBuilder2D::from_polygon(&comb, &[], Color::WHITE.into());
+    comb.reverse();
+    let reversed = MeshBuilder2D::from_polygon(&comb, &[], Color::WHITE.into());
+    assert!((clockwise_area(&forward) - 6.0).abs() < 1e-5);
+    assert!((clockwise_area(&reversed) - 6.0).abs() < 1e-5);
+}
+
+#[test]
+fn polygon_holes_are_left_empty() {
+    let outline = points(&[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]]);
+    let holes = vec![
+        points(&[[0.5, 0.5], [1.5, 0.5], [1.5, 1.5], [0.5, 1.5]]),
+        points(&[[2.0, 2.0], [3.5, 2.0], [2.75, 3.5]]),
+    ];
+    let mesh = MeshBuilder2D::from_polygon(&outline, &holes, Color::WHITE.into());
+    assert!(all_clockwise(&mesh));
+    assert!((clockwise_area(&mesh) - (16.0 - 1.0 - 1.125)).abs() < 1e-4);
+}
+
+#[test]
+fn polygon_uvs_span_the_bounding_box() {
+    let triangle = points(&[[-2.0, -1.0], [2.0, -1.0], [0.0, 3.0]]);
+    let mesh = MeshBuilder2D::from_polygon(&triangle, &[], Color::WHITE.into());
+    let apex = mesh.vertices.iter().find(|v| v.position == [0.0, 3.0]).unwrap();
+    let corner = mesh.vertices.iter().find(|v| v.position == [2.0, -1.0]).unwrap();
+    assert_eq!(apex.uv, [0.5, 0.0]);
+    assert_eq!(corner.uv, [1.0, 1.0]);
+}
+
+#[test]
+fn curved_shapes_cover_their_area() {
+    use std::f32::consts::PI;
+    let white: [f32; 4] = Color::WHITE.into();
+    let cases = [
+        (MeshBuilder2D::from_ellipse(2.0, 1.0, 256, white), 2.0 * PI),
+        (MeshBuilder2D::from_ring(1.0, 2.0, 256, white), 3.0 * PI),
+        (MeshBuilder2D::from_pie(2.0, 0.0, 90.0, 64, white), PI),
+        (MeshBuilder2D::from_rounded_rectangle(4.0, 2.0, [0.5, 0.0, 0.5, 0.0], 64, white), 8.0 - 2.0 * (1.0 - PI / 4.0) * 0.25),
+    ];
+    for (mesh, area) in cases {
+        assert!(all_clockwise(&mesh));
+        assert!((clockwise_area(&mesh) - area).abs() < 0.01, "expected {} but got {}", area, clockwise_area(&mesh));
+    }
+}
+
+#[test]
+fn rounded_rectangle_radii_are_limited_to_half_the_shorter_side() {
+    let mesh = MeshBuilder2D::from_rounded_rectangle(2.0, 2.0, [5.0; 4], 256, Color::WHITE.into());
+    assert!((clockwise_area(&mesh) - std::f32::consts::PI).abs() < 0.01);
+}
+
+#[test]
+fn arcs_are_stroked_along_the_radius() {
+    let stroke = Stroke::new(0.5);
+    let mesh = MeshBuilder2D::from_arc(1.0, 0.0, 180.0, 256, &stroke, Color::WHITE.into());
+    assert!(all_clockwise(&mesh));
+    for vertex in &mesh.vertices {
+        let [x, y] = vertex.position;
+        let distance = (x * x + y * y).sqrt();
+        assert!(distance > 0.749 && distance < 1.251 && y > -0.01, "{:?} is off the arc", vertex.position);
+    }
+}
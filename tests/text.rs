@@ -0,0 +1,37 @@
+use rgfx::prelude::*;
+
+fn font() -> Font {
+    Font::from_file("examples/assets/DejaVuSans.ttf").expect("Failed to load font")
+}
+
+#[test]
+fn invalid_font_is_an_error() {
+    assert!(Font::from_bytes(vec![0, 1, 2, 3]).is_err());
+    assert!(Font::from_file("does/not/exist.ttf").is_err());
+}
+
+#[test]
+fn measure_applies_kerning() {
+    let font = font();
+    let pair = font.measure("AV", 1.0).x;
+    let separate = font.measure("A", 1.0).x + font.measure("V", 1.0).x;
+    assert!(pair < separate);
+}
+
+#[test]
+fn measure_multiple_lines() {
+    let font = font();
+    let one = font.measure("rgfx", 1.0);
+    let two = font.measure("rgfx\nrgfx rgfx", 1.0);
+    assert!(two.x > one.x);
+    assert!((two.y - one.y - font.line_height(1.0)).abs() < 1e-4);
+}
+
+#[test]
+fn measure_scales_with_size() {
+    let font = font();
+    let small = font.measure("Hello", 1.0);
+    let large = font.measure("Hello", 2.0);
+    assert!((large.x - small.x * 2.0).abs() < 1e-4);
+    assert!((large.y - small.y * 2.0).abs() < 1e-4);
+}